@@ -0,0 +1,257 @@
+use std::sync::Arc;
+
+use crate::currency::Rates;
+use crate::env::Env;
+use crate::error::CwimError;
+use crate::function::F;
+use crate::interpreter::{self, Outcome};
+use crate::number::Number;
+use crate::parser::{self, Stmt};
+use crate::pratt;
+use crate::s::{self, Form, S};
+use crate::value::Value;
+use crate::word::Word;
+
+// The way to use cwim from other programs, e.g.
+//
+//     let mut calc = Calculator::new();
+//     calc.set_var("r", Number::from(2));
+//     calc.eval("pi r^2")?;
+pub struct Calculator {
+    env: Env<'static>,
+}
+
+impl Default for Calculator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Calculator {
+    pub fn new() -> Self {
+        Self {
+            env: Env::prelude(),
+        }
+    }
+
+    // Runs every `;`-separated statement in `text`, and returns the value of the last one
+    pub fn eval(&mut self, text: &str) -> Result<Value, CwimError> {
+        let mut result = None;
+        for (statement, _) in parser::statements(text) {
+            result = Some(match interpreter::run_stmt(statement, &mut self.env)? {
+                Outcome::Value(value) => {
+                    self.env.record(statement.trim(), value.clone());
+                    Ok(value)
+                }
+                Outcome::Pending(note) => Err(CwimError::Pending(note)),
+            });
+        }
+        result.unwrap_or_else(|| Err(CwimError::Invalid("Nothing to evaluate".to_owned())))
+    }
+
+    pub fn set_var(&mut self, name: &str, value: Number) {
+        self.env.assign(name.to_owned(), &value);
+    }
+
+    // The value of a variable or definition
+    pub fn var(&self, name: &str) -> Option<Value> {
+        self.env.value(name).ok()
+    }
+
+    // Adds a function of one argument, called like the built in ones: `f(x)` or `f x`
+    pub fn register_fn(
+        &mut self,
+        name: &str,
+        f: impl Fn(Number) -> Number + Send + Sync + 'static,
+    ) {
+        self.env
+            .register_unary(name, 4, Arc::new(move |x| Ok(f(x))));
+    }
+
+    // Parses an expression once, to be evaluated with different values for its names
+    pub fn compile(&self, text: &str) -> Result<CompiledExpr, CwimError> {
+        match parser::stmt(text, &self.env)? {
+            Stmt::Expr(mut tokens) => {
+                let expr = pratt::expr(&mut tokens, &self.env)?;
+                let mut unknowns = vec![];
+                collect_unknowns(&expr, &[], &mut unknowns);
+                Ok(CompiledExpr {
+                    source: text.to_owned(),
+                    expr,
+                    unknowns,
+                    word: self.env.settings.word,
+                    rates: self.env.rates.clone(),
+                })
+            }
+            _ => Err(CwimError::Invalid(format!(
+                "Expected an expression, found '{}'",
+                text
+            ))),
+        }
+    }
+
+    pub fn env(&self) -> &Env<'static> {
+        &self.env
+    }
+
+    pub fn env_mut(&mut self) -> &mut Env<'static> {
+        &mut self.env
+    }
+}
+
+// The names without a value, other than those bound inside `expr`, e.g. the
+// parameters of operators or k in sum(k^2, k, 1, n)
+fn collect_unknowns(expr: &S, bound: &[&str], unknowns: &mut Vec<String>) {
+    if let S::Unknown(name) = expr {
+        if !bound.contains(&name.as_str()) && !unknowns.contains(name) {
+            unknowns.push(name.clone());
+        }
+        return;
+    }
+    let mut collect = |args: &[S], bound: &[&str]| {
+        args.iter()
+            .for_each(|it| collect_unknowns(it, bound, unknowns))
+    };
+    match expr {
+        S::Name(name, _) if bound.contains(&name.as_str()) => {}
+        S::Name(_, s) => collect(std::slice::from_ref(s), bound),
+        S::Fun(_, args) | S::List(args) => collect(args, bound),
+        S::Call(fun, args, body) => {
+            collect(args, bound);
+            if let F::Defined(definition) = &fun.f {
+                let params: Vec<_> = definition.params.iter().map(String::as_str).collect();
+                collect(std::slice::from_ref(body), &[bound, &params].concat());
+            }
+        }
+        S::Form(Form::Sum | Form::Prod | Form::Limit | Form::Taylor, args) => match &args[..] {
+            [term, S::Unknown(x) | S::Name(x, _), rest @ ..] => {
+                collect(std::slice::from_ref(term), &[bound, &[x.as_str()]].concat());
+                collect(rest, bound);
+            }
+            _ => collect(args, bound),
+        },
+        S::Form(_, args) => collect(args, bound),
+        _ => {}
+    }
+}
+
+// An expression parsed by `Calculator::compile`, which doesn't borrow the
+// calculator or the text it was parsed from
+pub struct CompiledExpr {
+    source: String,
+    expr: S<'static>,
+    unknowns: Vec<String>,
+    // The word and rates of the calculator when it was compiled
+    word: Option<Word>,
+    rates: Rates,
+}
+
+impl CompiledExpr {
+    // The text it was parsed from
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    // The names without a value, which `eval` needs bindings for
+    pub fn unknowns(&self) -> &[String] {
+        &self.unknowns
+    }
+
+    // Evaluates with `bindings` for the unknowns, which can also override
+    // variables of the calculator.
+    pub fn eval(&self, bindings: &[(&str, Number)]) -> Result<Value, CwimError> {
+        let lookup = |name: &str| {
+            bindings
+                .iter()
+                .find(|(it, _)| *it == name)
+                .map(|(_, n)| n.clone())
+        };
+        if let Some(name) = self.unknowns.iter().find(|it| lookup(it).is_none()) {
+            return Err(CwimError::Unbound(name.to_string()));
+        }
+        let (value, _) = s::eval_word(&self.expr, &lookup, self.word, &self.rates)?;
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn _eval() {
+        let mut calc = Calculator::new();
+        calc.set_var("r", Number::from(2));
+        assert_eq!(calc.eval("a = 3r; a^2"), Ok(Value::from(Number::from(36))));
+        assert_eq!(calc.var("a"), Some(Value::from(Number::from(6))));
+        assert_eq!(calc.eval("ans + 1"), Ok(Value::from(Number::from(37))));
+        assert!(matches!(calc.eval("1 +"), Err(CwimError::Invalid(_))));
+        assert!(calc.eval("").is_err());
+    }
+
+    #[test]
+    fn _register_fn() {
+        let mut calc = Calculator::new();
+        let offset = Number::from(10);
+        calc.register_fn("shift", move |x| x + offset.clone());
+        assert_eq!(calc.eval("shift 5"), Ok(Value::from(Number::from(15))));
+        assert_eq!(calc.eval("2shift(1)^2"), Ok(Value::from(Number::from(242))));
+    }
+
+    #[test]
+    fn _compile() {
+        let mut calc = Calculator::new();
+        calc.set_var("k", Number::from(100));
+        let f = calc.compile(&String::from("k x^2 + y")).unwrap();
+        // Neither the text nor the calculator stay borrowed
+        calc.set_var("k", Number::from(7));
+        assert_eq!(f.source(), "k x^2 + y");
+        assert_eq!(f.unknowns(), ["x", "y"]);
+        let at = |x: i32, y: i32| f.eval(&[("x", Number::from(x)), ("y", Number::from(y))]);
+        assert_eq!(at(1, 2), Ok(Value::from(Number::from(102))));
+        assert_eq!(at(3, 0), Ok(Value::from(Number::from(900))));
+        assert_eq!(
+            f.eval(&[
+                ("x", Number::from(1)),
+                ("y", Number::from(0)),
+                ("k", Number::from(1))
+            ]),
+            Ok(Value::from(Number::from(1)))
+        );
+        assert_eq!(
+            f.eval(&[("x", Number::from(1))]),
+            Err(CwimError::Unbound("y".to_owned()))
+        );
+        let f = calc.compile("sum(k*x, k, 1, 3)").unwrap();
+        assert_eq!(f.unknowns(), ["x"]);
+        assert_eq!(
+            f.eval(&[("x", Number::from(2))]),
+            Ok(Value::from(Number::from(12)))
+        );
+    }
+
+    #[test]
+    fn _compile_names() {
+        let mut calc = Calculator::new();
+        // Stored without a value
+        assert!(matches!(
+            calc.eval("area := w*h"),
+            Err(CwimError::Pending(_))
+        ));
+        calc.env_mut().infix("<+> 5 (a, b) -> a + b + c").unwrap();
+        let f = calc.compile("area").unwrap();
+        assert_eq!(f.unknowns(), ["w", "h"]);
+        assert_eq!(
+            f.eval(&[("w", Number::from(2)), ("h", Number::from(3))]),
+            Ok(Value::from(Number::from(6)))
+        );
+        let f = calc.compile("x <+> y").unwrap();
+        assert_eq!(f.unknowns(), ["x", "y", "c"]);
+        let f = calc.compile("taylor(exp(a x), x, 0, 1)").unwrap();
+        assert_eq!(f.unknowns(), ["a"]);
+        // Integers wrap to the word of the calculator
+        calc.env_mut().settings.set("bits", "8").unwrap();
+        let f = calc.compile("x*2").unwrap();
+        assert_eq!(f.eval(&[("x", Number::from(128))]), calc.eval("128*2"));
+    }
+}
@@ -0,0 +1,640 @@
+use num::{One, ToPrimitive, Zero};
+
+use crate::function::*;
+use crate::number::Number;
+use crate::parser::Parsed;
+use crate::polynomial::Polynomial;
+use crate::s::{eval_with, Infix, Scope, S};
+
+// Derivatives, limits and Taylor series of expressions in x
+
+// How many times l'Hôpital's rule is applied before estimating the limit
+pub const MAX_DEPTH: usize = 8;
+
+// Whether `s` depends on x, a name bound when parsed counting as x too
+fn depends(s: &S, x: &str) -> bool {
+    match s {
+        S::Var(_) | S::Value(_) | S::Text(_) => false,
+        S::Unknown(name) | S::Name(name, _) => *name == x,
+        S::Fun(_, args) | S::List(args) | S::Call(_, args, _) | S::Form(_, args) => {
+            args.iter().any(|it| depends(it, x))
+        }
+    }
+}
+
+fn is(s: &S, n: i64) -> bool {
+    matches!(s, S::Var(m) if *m == Number::from(n))
+}
+
+// Operations which leave out adding 0 or multiplying by 1, so that
+// derivatives don't grow more than they have to
+
+fn add<'a>(a: S<'a>, b: S<'a>) -> S<'a> {
+    match (a, b) {
+        (S::Var(a), S::Var(b)) => S::Var(a + b),
+        (a, b) if is(&a, 0) => b,
+        (a, b) if is(&b, 0) => a,
+        (a, b) => S::Fun(ADD, vec![a, b]),
+    }
+}
+
+fn sub<'a>(a: S<'a>, b: S<'a>) -> S<'a> {
+    match (a, b) {
+        (S::Var(a), S::Var(b)) => S::Var(a - b),
+        (a, b) if is(&a, 0) => neg(b),
+        (a, b) if is(&b, 0) => a,
+        (a, b) => S::Fun(SUB, vec![a, b]),
+    }
+}
+
+fn neg(a: S) -> S {
+    match a {
+        S::Var(n) => S::Var(-n),
+        a => S::Fun(NEG, vec![a]),
+    }
+}
+
+fn mul<'a>(a: S<'a>, b: S<'a>) -> S<'a> {
+    match (a, b) {
+        (S::Var(a), S::Var(b)) => S::Var(a * b),
+        (a, _) | (_, a) if is(&a, 0) => S::Var(Number::zero()),
+        (a, b) if is(&a, 1) => b,
+        (a, b) if is(&b, 1) => a,
+        (a, b) => S::Fun(MUL, vec![a, b]),
+    }
+}
+
+fn div<'a>(a: S<'a>, b: S<'a>) -> S<'a> {
+    match (a, b) {
+        (a, _) if is(&a, 0) => S::Var(Number::zero()),
+        (a, b) if is(&b, 1) => a,
+        (a, b) => S::Fun(DIV, vec![a, b]),
+    }
+}
+
+fn pow<'a>(a: S<'a>, b: S<'a>) -> S<'a> {
+    match (a, b) {
+        (_, b) if is(&b, 0) => S::Var(Number::one()),
+        (a, b) if is(&b, 1) => a,
+        (a, b) => S::Fun(POW, vec![a, b]),
+    }
+}
+
+fn number<'a>(n: i64) -> S<'a> {
+    S::Var(Number::from(n))
+}
+
+// The derivative of `s` with respect to x
+pub fn derive<'a>(s: &S<'a>, x: &str) -> Parsed<S<'a>> {
+    if !depends(s, x) {
+        return Ok(number(0));
+    }
+    let (f, args) = match s {
+        S::Unknown(_) | S::Name(_, _) => return Ok(number(1)),
+        S::Fun(f, args) => (f, args),
+        S::Call(f, _, _) => return Err(format!("Can't differentiate {}", f.name)),
+        _ => return Err(format!("Can't differentiate {}", s)),
+    };
+    let d = |s: &S<'a>| derive(s, x);
+    Ok(match (f.name.as_ref(), &args[..]) {
+        ("+", [u]) => d(u)?,
+        ("-", [u]) => neg(d(u)?),
+        ("+", [u, v]) => add(d(u)?, d(v)?),
+        ("-", [u, v]) => sub(d(u)?, d(v)?),
+        ("*", [u, v]) => add(mul(d(u)?, v.clone()), mul(u.clone(), d(v)?)),
+        ("/", [u, v]) => {
+            let top = sub(mul(d(u)?, v.clone()), mul(u.clone(), d(v)?));
+            div(top, pow(v.clone(), number(2)))
+        }
+        // Exponents that don't depend on x, then bases that don't
+        ("^", [u, v]) if !depends(v, x) => {
+            let power = pow(u.clone(), sub(v.clone(), number(1)));
+            mul(mul(v.clone(), power), d(u)?)
+        }
+        ("^", [u, v]) if !depends(u, x) => mul(mul(s.clone(), S::Fun(LN, vec![u.clone()])), d(v)?),
+        // u^v = exp(v ln u)
+        ("^", [u, v]) => {
+            let inner = add(
+                mul(d(v)?, S::Fun(LN, vec![u.clone()])),
+                div(mul(v.clone(), d(u)?), u.clone()),
+            );
+            mul(s.clone(), inner)
+        }
+        (name, [u]) => {
+            let u = u.clone();
+            let outer = match name {
+                "sqrt" => div(number(1), mul(number(2), s.clone())),
+                "cbrt" => div(number(1), mul(number(3), pow(s.clone(), number(2)))),
+                "sin" => S::Fun(COS, vec![u.clone()]),
+                "cos" => neg(S::Fun(SIN, vec![u.clone()])),
+                "tan" => div(number(1), pow(S::Fun(COS, vec![u.clone()]), number(2))),
+                "sinh" => S::Fun(COSH, vec![u.clone()]),
+                "cosh" => S::Fun(SINH, vec![u.clone()]),
+                "tanh" => div(number(1), pow(S::Fun(COSH, vec![u.clone()]), number(2))),
+                "asin" | "acos" => {
+                    let root = S::Fun(SQRT, vec![sub(number(1), pow(u.clone(), number(2)))]);
+                    let d = div(number(1), root);
+                    if name == "acos" {
+                        neg(d)
+                    } else {
+                        d
+                    }
+                }
+                "atan" => div(number(1), add(number(1), pow(u.clone(), number(2)))),
+                "asinh" => div(
+                    number(1),
+                    S::Fun(SQRT, vec![add(pow(u.clone(), number(2)), number(1))]),
+                ),
+                "acosh" => div(
+                    number(1),
+                    S::Fun(SQRT, vec![sub(pow(u.clone(), number(2)), number(1))]),
+                ),
+                "atanh" => div(number(1), sub(number(1), pow(u.clone(), number(2)))),
+                "exp" => s.clone(),
+                "ln" => div(number(1), u.clone()),
+                "log" => div(number(1), mul(u.clone(), S::Fun(LN, vec![number(10)]))),
+                name => return Err(format!("Can't differentiate {}", name)),
+            };
+            mul(outer, d(&u)?)
+        }
+        (name, _) => return Err(format!("Can't differentiate {}", name)),
+    })
+}
+
+// Floats which are whole numbers, e.g. cos 0, are made exact, so that Taylor
+// series of sin or exp have rational coefficients
+fn exact(n: Number) -> Number {
+    match n {
+        Number::Flt(f) if f.fract() == 0. && f.abs() < 2f64.powi(53) => Number::from(f as i64),
+        n => n.try_into_int(),
+    }
+}
+
+// The value at x = a, made exact after every function, e.g. in cos(x)/2
+fn value(s: &S, x: &str, a: &Number, scope: &Scope) -> Parsed<Number> {
+    match s {
+        S::Unknown(name) | S::Name(name, _) if *name == x => Ok(a.clone()),
+        S::Fun(f, args) => {
+            let args = args
+                .iter()
+                .map(|it| value(it, x, a, scope).map(S::Var))
+                .collect::<Parsed<Vec<_>>>()?;
+            // Undefined stays so, even though 1^NaN is 1 for floats
+            if args.iter().any(|it| matches!(it, S::Var(n) if n.is_nan())) {
+                return Ok(Number::Flt(f64::NAN));
+            }
+            scope.at(&S::Fun(f.clone(), args), x, a.clone()).map(exact)
+        }
+        s => scope.at(s, x, a.clone()).map(exact),
+    }
+}
+
+fn is_finite(n: &Number) -> bool {
+    n.to_f64().is_some_and(f64::is_finite)
+}
+
+// The limit of `s` when x goes to `a`, which can be inf. Quotients of 0 or
+// infinities are found by l'Hôpital's rule, and anything else numerically.
+pub fn limit(s: &S, x: &str, a: &Number, scope: &Scope) -> Parsed<Number> {
+    lhopital(s, x, a, 0, scope).or_else(|_| estimate(s, x, a, scope))
+}
+
+fn lhopital(s: &S, x: &str, a: &Number, depth: usize, scope: &Scope) -> Parsed<Number> {
+    // At infinity, floats give 1 for (1 + 1/x)^x, so only quotients are trusted
+    if is_finite(a) || !depends(s, x) {
+        let at_a = value(s, x, a, scope)?;
+        if is_finite(&at_a) {
+            return Ok(at_a);
+        }
+    }
+    match s {
+        S::Fun(f, args) if *f == DIV && args.len() == 2 && depth < MAX_DEPTH => {
+            let (u, v) = (&args[0], &args[1]);
+            let (top, bottom) = (value(u, x, a, scope)?, value(v, x, a, scope)?);
+            let indeterminate = (top.is_zero() && bottom.is_zero())
+                || (!is_finite(&top) && !is_finite(&bottom) && !top.is_nan() && !bottom.is_nan());
+            if !indeterminate {
+                return Err(format!("{} isn't a quotient of 0 or infinities", s));
+            }
+            let quotient = div(derive(u, x)?, derive(v, x)?);
+            lhopital(&quotient, x, a, depth + 1, scope)
+        }
+        S::Name(_, inner) => lhopital(inner, x, a, depth, scope),
+        _ => Err(format!("{} isn't a quotient", s)),
+    }
+}
+
+// The limit from the values at x = a ± 2^-k, or x = 2^k for inf, up to k = 40, improved by
+// Richardson extrapolation assuming the error is proportional to the step.
+// Values which keep growing are taken to go to infinity.
+fn estimate(s: &S, x: &str, a: &Number, scope: &Scope) -> Parsed<Number> {
+    let a = a.to_f64().unwrap_or(f64::NAN);
+    let side = |sign: f64| -> Parsed<f64> {
+        let (mut table, mut values): (Vec<f64>, Vec<f64>) = (vec![], vec![]);
+        let mut previous = f64::NAN;
+        for k in 4..=40 {
+            let step = 2f64.powi(-k);
+            let at = if a.is_infinite() {
+                a.signum() / step
+            } else {
+                a + sign * step
+            };
+            let y = scope.at(s, x, Number::Flt(at))?.f64_or_nan();
+            values.push(y);
+            let mut next = vec![y];
+            for (j, previous) in table.iter().enumerate() {
+                let factor = 2f64.powi(j as i32 + 1);
+                next.push((factor * next[j] - previous) / (factor - 1.));
+            }
+            previous = table.get(4).copied().unwrap_or(f64::NAN);
+            table = next;
+        }
+        let last = values[values.len() - 1];
+        if last.abs() > 1e6 && last.abs() > 1e3 * values[values.len() - 11].abs() {
+            return Ok(last.signum() * f64::INFINITY);
+        }
+        // Estimates which don't settle, e.g. for ln x at 0, aren't limits
+        let estimate = table.get(4).copied().unwrap_or(f64::NAN);
+        match (estimate - previous).abs() <= 1e-6 * estimate.abs().max(1.) {
+            true => Ok(estimate),
+            false => Ok(f64::NAN),
+        }
+    };
+    let right = side(1.)?;
+    let left = if a.is_infinite() { right } else { side(-1.)? };
+    // Functions only defined on one side, e.g. x ln x at 0
+    let (left, right) = match (left.is_nan(), right.is_nan()) {
+        (true, false) => (right, right),
+        (false, true) => (left, left),
+        _ => (left, right),
+    };
+    let close =
+        left == right || left.is_finite() && (left - right).abs() <= 1e-6 * left.abs().max(1.);
+    match (!left.is_nan() && !right.is_nan(), close) {
+        (true, true) if left.abs() < 1e-10 => Ok(Number::zero()),
+        (true, true) => Ok(exact(Number::Flt((left + right) / 2.))),
+        (true, false) => Err(format!(
+            "The limit from the left, {}, isn't the one from the right, {}",
+            left, right
+        )),
+        _ => Err("No limit found".to_owned()),
+    }
+}
+
+// Truncated power series in x - a are their coefficients from the constant
+// one up, which operations keep to the same order
+
+fn constant(c: Number, n: usize) -> Vec<Number> {
+    let mut result = vec![Number::zero(); n + 1];
+    result[0] = c;
+    result
+}
+
+fn series_add(u: &[Number], v: &[Number]) -> Vec<Number> {
+    u.iter()
+        .zip(v)
+        .map(|(a, b)| a.clone() + b.clone())
+        .collect()
+}
+
+fn series_neg(u: &[Number]) -> Vec<Number> {
+    u.iter().map(|it| -it.clone()).collect()
+}
+
+fn series_mul(u: &[Number], v: &[Number]) -> Vec<Number> {
+    (0..u.len())
+        .map(|k| {
+            (0..=k).fold(Number::zero(), |acc, j| {
+                acc + u[j].clone() * v[k - j].clone()
+            })
+        })
+        .collect()
+}
+
+// Where v starts with 0, the quotient isn't defined, unless u does too
+fn series_div(u: &[Number], v: &[Number]) -> Vec<Number> {
+    let mut q: Vec<Number> = vec![];
+    for k in 0..u.len() {
+        let rest = (1..=k).fold(Number::zero(), |acc, j| {
+            acc + v[j].clone() * q[k - j].clone()
+        });
+        q.push((u[k].clone() - rest) / v[0].clone());
+    }
+    q
+}
+
+// The derivative, known up to one order less
+fn series_derive(u: &[Number]) -> Vec<Number> {
+    (1..u.len())
+        .map(|k| Number::from(k) * u[k].clone())
+        .collect()
+}
+
+// The antiderivative which is c at a, known up to one order more
+fn series_integrate(c: Number, d: &[Number]) -> Vec<Number> {
+    let rest = d
+        .iter()
+        .enumerate()
+        .map(|(k, it)| it.clone() / Number::from(k + 1));
+    std::iter::once(c).chain(rest).collect()
+}
+
+// u^r for a constant r, from (u^r)' u = r u' u^r when u doesn't start with 0
+fn series_pow(u: &[Number], r: &Number) -> Parsed<Vec<Number>> {
+    if let Number::Int(m) = r {
+        if let Some(m) = m.to_u32() {
+            let (mut result, mut power) = (constant(Number::one(), u.len() - 1), u.to_vec());
+            let mut m = m;
+            while m > 0 {
+                if m & 1 == 1 {
+                    result = series_mul(&result, &power);
+                }
+                power = series_mul(&power, &power);
+                m >>= 1;
+            }
+            return Ok(result);
+        }
+    }
+    let mut p = vec![apply(&POW, &[u[0].clone(), r.clone()])?];
+    for k in 1..u.len() {
+        let sum = (1..=k).fold(Number::zero(), |acc, j| {
+            let factor = (r.clone() + Number::one()) * Number::from(j) - Number::from(k);
+            acc + factor * u[j].clone() * p[k - j].clone()
+        });
+        p.push(sum / (Number::from(k) * u[0].clone()));
+    }
+    Ok(p)
+}
+
+// exp(u), from exp(u)' = u' exp(u)
+fn series_exp(u: &[Number]) -> Parsed<Vec<Number>> {
+    let mut e = vec![apply(&EXP, &u[..1])?];
+    for k in 1..u.len() {
+        let sum = (1..=k).fold(Number::zero(), |acc, j| {
+            acc + Number::from(j) * u[j].clone() * e[k - j].clone()
+        });
+        e.push(sum / Number::from(k));
+    }
+    Ok(e)
+}
+
+// sin(u) and cos(u), or sinh(u) and cosh(u), from the derivative of each
+// being the other times u', but for the sign of cos
+fn series_sin_cos(u: &[Number], hyperbolic: bool) -> Parsed<(Vec<Number>, Vec<Number>)> {
+    let (sin, cos) = match hyperbolic {
+        true => (SINH, COSH),
+        false => (SIN, COS),
+    };
+    let mut s = vec![apply(&sin, &u[..1])?];
+    let mut c = vec![apply(&cos, &u[..1])?];
+    for k in 1..u.len() {
+        let term = |other: &[Number]| {
+            (1..=k).fold(Number::zero(), |acc, j| {
+                acc + Number::from(j) * u[j].clone() * other[k - j].clone()
+            }) / Number::from(k)
+        };
+        let (next_s, next_c) = (term(&c), term(&s));
+        s.push(next_s);
+        c.push(if hyperbolic { next_c } else { -next_c });
+    }
+    Ok((s, c))
+}
+
+// The value of `f` for these arguments, made exact
+fn apply(f: &Function, args: &[Number]) -> Parsed<Number> {
+    let args = args.iter().cloned().map(S::Var).collect();
+    eval_with(&S::Fun(f.clone(), args), &|_| None).map(exact)
+}
+
+// The series of `s` up to (x - a)^n
+fn expand(s: &S, x: &str, a: &Number, n: usize, scope: &Scope) -> Parsed<Vec<Number>> {
+    if !depends(s, x) {
+        return Ok(constant(value(s, x, a, scope)?, n));
+    }
+    let (f, args) = match s {
+        S::Unknown(_) | S::Name(_, _) => {
+            let mut result = constant(a.clone(), n);
+            if n > 0 {
+                result[1] = Number::one();
+            }
+            return Ok(result);
+        }
+        S::Fun(f, args) => (f, args),
+        _ => return Err(format!("Can't find the Taylor series of {}", s)),
+    };
+    let e = |s: &S| expand(s, x, a, n, scope);
+    Ok(match (f.name.as_ref(), &args[..]) {
+        ("+", [u]) => e(u)?,
+        ("-", [u]) => series_neg(&e(u)?),
+        ("+", [u, v]) => series_add(&e(u)?, &e(v)?),
+        ("-", [u, v]) => series_add(&e(u)?, &series_neg(&e(v)?)),
+        ("*", [u, v]) => series_mul(&e(u)?, &e(v)?),
+        ("/", [u, v]) => quotient(u, v, x, a, n, scope)?,
+        ("^", [u, v]) if !depends(v, x) => series_pow(&e(u)?, &value(v, x, a, scope)?)?,
+        // u^v = exp(v ln u)
+        ("^", [u, v]) => {
+            let ln = S::Fun(LN, vec![u.clone()]);
+            series_exp(&series_mul(&e(v)?, &e(&ln)?))?
+        }
+        (name, [u]) => {
+            let u = e(u)?;
+            let du = series_derive(&u);
+            let u0 = &u[..1];
+            let square = series_mul(&u, &u);
+            let one = constant(Number::one(), n);
+            let half = Number::one() / Number::from(2);
+            match name {
+                "sqrt" => series_pow(&u, &half)?,
+                "cbrt" => series_pow(&u, &(Number::one() / Number::from(3)))?,
+                "exp" => series_exp(&u)?,
+                "sin" => series_sin_cos(&u, false)?.0,
+                "cos" => series_sin_cos(&u, false)?.1,
+                "tan" => {
+                    let (sin, cos) = series_sin_cos(&u, false)?;
+                    series_div(&sin, &cos)
+                }
+                "sinh" => series_sin_cos(&u, true)?.0,
+                "cosh" => series_sin_cos(&u, true)?.1,
+                "tanh" => {
+                    let (sinh, cosh) = series_sin_cos(&u, true)?;
+                    series_div(&sinh, &cosh)
+                }
+                // The others are the antiderivatives of u' times a series of u
+                "ln" => series_integrate(apply(&LN, u0)?, &series_div(&du, &u)),
+                "log" => {
+                    let ln10 = constant(apply(&LN, &[Number::from(10)])?, n);
+                    let d = series_div(&du, &series_mul(&u, &ln10));
+                    series_integrate(apply(&LOG, u0)?, &d)
+                }
+                "atan" => {
+                    let d = series_div(&du, &series_add(&one, &square));
+                    series_integrate(apply(&ATAN, u0)?, &d)
+                }
+                "asin" | "acos" => {
+                    let root = series_pow(&series_add(&one, &series_neg(&square)), &half)?;
+                    let d = series_div(&du, &root);
+                    match name {
+                        "acos" => series_integrate(apply(&ACOS, u0)?, &series_neg(&d)),
+                        _ => series_integrate(apply(&ASIN, u0)?, &d),
+                    }
+                }
+                "asinh" => {
+                    let root = series_pow(&series_add(&square, &one), &half)?;
+                    series_integrate(apply(&ASINH, u0)?, &series_div(&du, &root))
+                }
+                "acosh" => {
+                    let root = series_pow(&series_add(&square, &series_neg(&one)), &half)?;
+                    series_integrate(apply(&ACOSH, u0)?, &series_div(&du, &root))
+                }
+                "atanh" => {
+                    let d = series_div(&du, &series_add(&one, &series_neg(&square)));
+                    series_integrate(apply(&ATANH, u0)?, &d)
+                }
+                name => return Err(format!("Can't find the Taylor series of {}", name)),
+            }
+        }
+        (name, _) => return Err(format!("Can't find the Taylor series of {}", name)),
+    })
+}
+
+// u / v, where both may start with as many zeros, e.g. sin(x)/x at 0. They
+// are then expanded further, so that the quotient still goes up to (x - a)^n.
+fn quotient(u: &S, v: &S, x: &str, a: &Number, n: usize, scope: &Scope) -> Parsed<Vec<Number>> {
+    let bottom = expand(v, x, a, n, scope)?;
+    let zeros = bottom.iter().take_while(|it| it.is_zero()).count();
+    if zeros == 0 {
+        return Ok(series_div(&expand(u, x, a, n, scope)?, &bottom));
+    }
+    if zeros > n {
+        return Err(format!("{} is 0 up to order {} at {}", Infix(v), n, a));
+    }
+    let (top, bottom) = (
+        expand(u, x, a, n + zeros, scope)?,
+        expand(v, x, a, n + zeros, scope)?,
+    );
+    if !top[..zeros].iter().all(|it| it.is_zero()) {
+        return Err(format!(
+            "{} isn't defined at {}",
+            Infix(&S::Fun(DIV, vec![u.clone(), v.clone()])),
+            a
+        ));
+    }
+    Ok(series_div(&top[zeros..], &bottom[zeros..]))
+}
+
+// The Taylor series of `s` at x = a up to (x - a)^n, as a polynomial in x,
+// with coefficients that are rational when the values at a are. It's found
+// with truncated series, as derivatives grow too fast, e.g. those of tan x.
+pub fn taylor(s: &S, x: &str, a: &Number, n: usize, scope: &Scope) -> Parsed<Polynomial> {
+    let coefs = expand(s, x, a, n, scope)?;
+    if let Some(k) = coefs
+        .iter()
+        .position(|it| matches!(it, Number::Flt(x) if !x.is_finite()))
+    {
+        return Err(format!(
+            "The derivative of order {} isn't defined at {}",
+            k, a
+        ));
+    }
+    let coefs = coefs.into_iter().map(|it| it.try_into_int()).collect();
+    let p = Polynomial::with_coefs(x, coefs);
+    // A series at 1 is in powers of x - 1
+    match a.is_zero() || p.variables().is_empty() {
+        true => Ok(p),
+        false => p.substitute(&[Polynomial::variable(x) + -a.clone()]),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::env::Env;
+    use crate::parser;
+    use crate::pratt;
+
+    fn parse<'a>(text: &'a str, env: &'a Env) -> S<'a> {
+        let mut tokens = parser::stmt(text, env).unwrap().rhs().clone();
+        pratt::expr(&mut tokens, env).unwrap()
+    }
+
+    fn ratio(n: i64, d: i64) -> Number {
+        Number::from(n) / Number::from(d)
+    }
+
+    #[test]
+    fn _derive() {
+        let env = Env::prelude();
+        let at = |text: &str, x: Number| {
+            let d = derive(&parse(text, &env), "x").unwrap();
+            Scope::empty(|scope| value(&d, "x", &x, scope)).unwrap()
+        };
+        assert_eq!(at("3x^2 + 2x", Number::from(2)), Number::from(14));
+        assert_eq!(at("1/x", Number::from(2)), ratio(-1, 4));
+        assert_eq!(at("sin x * cos x", Number::from(0)), Number::from(1));
+        assert_eq!(at("x^x", Number::from(1)), Number::from(1));
+        assert_eq!(at("ln(x^2 + 1)", Number::from(1)), Number::from(1));
+        assert!(derive(&parse("x % 2", &env), "x").is_err());
+    }
+
+    #[test]
+    fn _limit() {
+        let env = Env::prelude();
+        let limit_at =
+            |text: &str, a: Number| Scope::empty(|scope| limit(&parse(text, &env), "x", &a, scope));
+        assert_eq!(limit_at("sin(x)/x", Number::from(0)), Ok(Number::from(1)));
+        assert_eq!(
+            limit_at("(1 - cos x)/x^2", Number::from(0)),
+            Ok(ratio(1, 2))
+        );
+        assert_eq!(limit_at("x^2 + 1", Number::from(3)), Ok(Number::from(10)));
+        assert_eq!(
+            limit_at("(x^2 + 1)/(2x^2)", Number::Flt(f64::INFINITY)),
+            Ok(ratio(1, 2))
+        );
+        assert!(limit_at("sqrt(x^2)/x", Number::from(0)).is_err());
+        let e = limit_at("(1 + 1/x)^x", Number::Flt(f64::INFINITY)).unwrap();
+        assert!((e.to_f64().unwrap() - std::f64::consts::E).abs() < 1e-6);
+        assert_eq!(limit_at("x ln x", Number::from(0)), Ok(Number::from(0)));
+    }
+
+    #[test]
+    fn _taylor() {
+        let env = Env::prelude();
+        let coefs = |text: &str, n| {
+            let p =
+                Scope::empty(|scope| taylor(&parse(text, &env), "x", &Number::from(0), n, scope));
+            let p = p.unwrap();
+            p.coefs().unwrap()
+        };
+        assert_eq!(
+            coefs("exp x", 4),
+            vec![
+                Number::from(1),
+                Number::from(1),
+                ratio(1, 2),
+                ratio(1, 6),
+                ratio(1, 24)
+            ]
+        );
+        assert_eq!(
+            coefs("sin x", 5),
+            vec![
+                Number::from(0),
+                Number::from(1),
+                Number::from(0),
+                ratio(-1, 6),
+                Number::from(0),
+                ratio(1, 120)
+            ]
+        );
+        assert_eq!(
+            coefs("sin(x)/x", 2)[..2],
+            [Number::from(1), Number::from(0)]
+        );
+        assert_eq!(coefs("tan x", 7)[7], ratio(17, 315));
+        assert_eq!(coefs("1/(1 - x)", 3), vec![Number::from(1); 4]);
+        assert_eq!(coefs("sqrt(1 + x)", 2)[2], ratio(-1, 8));
+        let at_0 = |scope: &Scope| taylor(&parse("ln x", &env), "x", &Number::from(0), 2, scope);
+        assert!(Scope::empty(at_0).is_err());
+    }
+}
@@ -0,0 +1,532 @@
+use std::collections::BTreeMap;
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use crate::currency::Rates;
+use crate::env::{Env, Functions, Variable};
+use crate::function::{Function, F};
+use crate::number::Number;
+use crate::parser::{self, Parsed, Stmt};
+use crate::pratt;
+use crate::s;
+use crate::session;
+
+// What the REPL should do after a command.
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Done,
+    Print(String),
+    Quit,
+}
+
+// A `:`-prefixed REPL command, e.g. `:clear x`.
+pub trait Command {
+    // What follows the `:`
+    fn name(&self) -> &str;
+    // The arguments it takes, as shown by `:help`
+    fn args(&self) -> &str {
+        ""
+    }
+    // One line shown by `:help`
+    fn help(&self) -> &str;
+    fn run(&self, args: &str, env: &mut Env) -> Parsed<Outcome>;
+}
+
+struct Builtin {
+    name: &'static str,
+    args: &'static str,
+    help: &'static str,
+    run: fn(&str, &mut Env) -> Parsed<Outcome>,
+}
+
+impl Command for Builtin {
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn args(&self) -> &str {
+        self.args
+    }
+    fn help(&self) -> &str {
+        self.help
+    }
+    fn run(&self, args: &str, env: &mut Env) -> Parsed<Outcome> {
+        (self.run)(args, env)
+    }
+}
+
+pub struct Commands {
+    commands: Vec<Box<dyn Command>>,
+}
+
+impl Default for Commands {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl Commands {
+    pub fn builtin() -> Self {
+        let builtins = [
+            Builtin {
+                name: "vars",
+                args: "",
+                help: "List variables and definitions",
+                run: vars,
+            },
+            Builtin {
+                name: "funcs",
+                args: "",
+                help: "List functions by category",
+                run: funcs,
+            },
+            Builtin {
+                name: "type",
+                args: "EXPR",
+                help: "Show the type of the value of EXPR",
+                run: type_of,
+            },
+            Builtin {
+                name: "infix",
+                args: "OP PRIORITY [left|right] (A, B) -> EXPR",
+                help: "Define an operator, e.g. :infix <+> 5 (a, b) -> sqrt(a^2+b^2)",
+                run: |args, env| {
+                    env.infix(args)?;
+                    Ok(Outcome::Done)
+                },
+            },
+            Builtin {
+                name: "bits",
+                args: "[N [signed|unsigned] | off]",
+                help: "Wrap integers to N bits after every operation, or show the word size",
+                run: |args, env| match args {
+                    "" => Ok(Outcome::Print(env.settings.get("bits")?)),
+                    _ => {
+                        env.settings.set("bits", args)?;
+                        Ok(Outcome::Done)
+                    }
+                },
+            },
+            Builtin {
+                name: "deps",
+                args: "NAME",
+                help: "List what the definition of NAME depends on",
+                run: deps,
+            },
+            Builtin {
+                name: "clear",
+                args: "NAME",
+                help: "Remove a variable, or restore it if it was built in",
+                run: clear,
+            },
+            Builtin {
+                name: "reset",
+                args: "",
+                help: "Remove every variable and result",
+                run: |_, env| {
+                    env.reset();
+                    Ok(Outcome::Done)
+                },
+            },
+            Builtin {
+                name: "history",
+                args: "",
+                help: "List every result with its input",
+                run: history,
+            },
+            Builtin {
+                name: "set",
+                args: "[KEY [VALUE]]",
+                help: "Show or change settings",
+                run: set,
+            },
+            Builtin {
+                name: "save",
+                args: "NAME",
+                help: "Save variables and settings to NAME.cwim-session",
+                run: |args, env| {
+                    session::save(env, &session::path(one_arg(args)?))?;
+                    Ok(Outcome::Done)
+                },
+            },
+            Builtin {
+                name: "load",
+                args: "NAME",
+                help: "Load variables and settings from NAME.cwim-session",
+                run: |args, env| {
+                    session::load(env, &session::path(one_arg(args)?))?;
+                    Ok(Outcome::Done)
+                },
+            },
+            Builtin {
+                name: "rates",
+                args: "[FILE]",
+                help: "List exchange rates, or add those of a .csv or .toml file",
+                run: rates,
+            },
+            Builtin {
+                name: "quit",
+                args: "",
+                help: "Leave cwim",
+                run: |_, _| Ok(Outcome::Quit),
+            },
+        ];
+        let mut commands = Self { commands: vec![] };
+        for builtin in builtins {
+            commands.register(Box::new(builtin));
+        }
+        commands
+    }
+
+    // Adds a command, replacing any other one with the same name.
+    pub fn register(&mut self, command: Box<dyn Command>) {
+        self.commands.retain(|it| it.name() != command.name());
+        self.commands.push(command);
+    }
+
+    // Runs a line such as `:clear x`.
+    pub fn run(&self, line: &str, env: &mut Env) -> Parsed<Outcome> {
+        let line = line.trim();
+        let line = line.strip_prefix(':').unwrap_or(line);
+        let (name, args) = line.split_once(' ').unwrap_or((line, ""));
+        if name == "help" {
+            return self.help(args.trim(), env).map(Outcome::Print);
+        }
+        match self.commands.iter().find(|it| it.name() == name) {
+            Some(command) => command.run(args.trim(), env),
+            None => Err(format!("Unknown command ':{}', try :help", name)),
+        }
+    }
+
+    fn help(&self, topic: &str, env: &Env) -> Parsed<String> {
+        if topic.is_empty() {
+            let mut lines = vec![format!(
+                "{:<22}{}",
+                ":help [NAME]", "Show this, or help on NAME"
+            )];
+            for command in &self.commands {
+                let usage = format!(":{} {}", command.name(), command.args());
+                lines.push(format!("{:<22}{}", usage.trim_end(), command.help()));
+            }
+            return Ok(lines.join("\n"));
+        }
+        let name = topic.strip_prefix(':').unwrap_or(topic);
+        if let Some(command) = self.commands.iter().find(|it| it.name() == name) {
+            let usage = format!(":{} {}", command.name(), command.args());
+            return Ok(format!("{}\n{}", usage.trim_end(), command.help()));
+        }
+        match env.get(topic) {
+            Some(Variable::Function(fs)) => Ok(describe(env, topic, fs)),
+            Some(Variable::Value(n)) => Ok(format!("{} = {}", topic, n)),
+            Some(Variable::Lazy(body)) => Ok(format!("{} := {}", topic, body)),
+            None => Err(format!("No help for '{}'", topic)),
+        }
+    }
+}
+
+// The command a line stands for, if any: `:clear x`, or the shorthands
+// `help sin`, `? sin` and `deps x`. These only take a name known to `env`, so
+// that expressions using variables called help or deps stay expressions.
+pub fn command_line(line: &str, env: &Env) -> Option<String> {
+    let line = line.trim();
+    if line.starts_with(':') {
+        return Some(line.to_owned());
+    }
+    if let Some(rest) = line.strip_prefix('?') {
+        return topic(rest, env).map(|it| format!(":help {}", it));
+    }
+    match line.split_once(' ').unwrap_or((line, "")) {
+        (word @ ("help" | "deps"), _) if env.get(word).is_some() => None,
+        ("help", rest) => topic(rest, env).map(|it| format!(":help {}", it)),
+        ("deps", rest) => match topic(rest, env)? {
+            "" => None,
+            name => Some(format!(":deps {}", name)),
+        },
+        _ => None,
+    }
+}
+
+// The name after a shorthand, if it's nothing, a command or a name in `env`
+fn topic<'l>(name: &'l str, env: &Env) -> Option<&'l str> {
+    let name = name.trim();
+    let known = name.is_empty() || name.starts_with(':') || env.get(name).is_some();
+    Some(name).filter(|_| known && !name.contains(char::is_whitespace))
+}
+
+// Runs a line if it's a command, e.g. `:quit`, and says whether to go on.
+pub fn run_command(
+    commands: &Commands,
+    line: &str,
+    env: &mut Env,
+) -> Option<Parsed<ControlFlow<()>>> {
+    let line = command_line(line, env)?;
+    Some(commands.run(&line, env).map(|outcome| match outcome {
+        Outcome::Done => ControlFlow::Continue(()),
+        Outcome::Print(text) => {
+            println!("{}", text);
+            ControlFlow::Continue(())
+        }
+        Outcome::Quit => ControlFlow::Break(()),
+    }))
+}
+
+// Every name `f` goes by, e.g. `acos` and `arccos`.
+fn names<'e>(env: &'e Env, f: &Function) -> Vec<&'e str> {
+    let mut result: Vec<_> = env
+        .functions()
+        .into_iter()
+        .filter(|(_, fs)| fs.get(f.arity).as_ref() == Some(f))
+        .map(|(name, _)| name)
+        .collect();
+    result.sort_by_key(|name| *name != f.name);
+    result
+}
+
+fn summary(f: &Function) -> String {
+    match &f.f {
+        F::Defined(definition) => format!("Defined as {}", definition.body),
+        _ => f.doc.summary.to_owned(),
+    }
+}
+
+fn describe(env: &Env, name: &str, fs: &Functions) -> String {
+    let mut blocks = vec![];
+    for f in [&fs.unary, &fs.binary].into_iter().flatten() {
+        let mut lines = vec![f.usage(name), summary(f)];
+        let aliases: Vec<_> = names(env, f).into_iter().filter(|it| *it != name).collect();
+        if !aliases.is_empty() {
+            lines.push(format!("Also called {}", aliases.join(", ")));
+        }
+        if !f.doc.domain.is_empty() {
+            lines.push(format!("Defined for {}", f.doc.domain));
+        }
+        for (input, value) in f.doc.examples {
+            lines.push(format!("  {} = {}", input, value));
+        }
+        blocks.push(lines.join("\n"));
+    }
+    blocks.join("\n\n")
+}
+
+fn one_arg(args: &str) -> Parsed<&str> {
+    match args.split_whitespace().collect::<Vec<_>>()[..] {
+        [arg] => Ok(arg),
+        _ => Err(format!("Expected one argument, found '{}'", args)),
+    }
+}
+
+fn vars(_: &str, env: &mut Env) -> Parsed<Outcome> {
+    let lines: Vec<_> = env
+        .user_variables()
+        .into_iter()
+        .map(|(name, var)| match var {
+            Variable::Value(n) => format!("{} = {}", name, n),
+            Variable::Lazy(body) => format!("{} := {}", name, body),
+            Variable::Function(_) => name.to_owned(),
+        })
+        .collect();
+    Ok(Outcome::Print(lines.join("\n")))
+}
+
+fn funcs(_: &str, env: &mut Env) -> Parsed<Outcome> {
+    let mut by_category = BTreeMap::new();
+    for (_, fs) in env.functions() {
+        for f in [&fs.unary, &fs.binary].into_iter().flatten() {
+            let usages: Vec<_> = names(env, f)
+                .into_iter()
+                .map(|name| f.usage(name))
+                .collect();
+            by_category
+                .entry(f.doc.category)
+                .or_insert_with(BTreeMap::new)
+                .insert(
+                    (f.arity, f.priority, f.name.clone()),
+                    (usages.join(", "), summary(f)),
+                );
+        }
+    }
+    let mut lines = vec![];
+    for (category, functions) in by_category {
+        lines.push(category.to_string());
+        for (usage, summary) in functions.into_values() {
+            lines.push(format!("  {:<22}{}", usage, summary));
+        }
+    }
+    Ok(Outcome::Print(lines.join("\n")))
+}
+
+fn type_of(args: &str, env: &mut Env) -> Parsed<Outcome> {
+    match parser::stmt(args, env)? {
+        Stmt::Expr(mut tokens) => {
+            let value = s::eval_value(&pratt::expr(&mut tokens, env)?)?;
+            Ok(Outcome::Print(value.type_name().to_owned()))
+        }
+        _ => Err(format!("Expected an expression, found '{}'", args)),
+    }
+}
+
+fn deps(args: &str, env: &mut Env) -> Parsed<Outcome> {
+    let name = one_arg(args)?;
+    let deps = env.deps(name)?;
+    if deps.is_empty() {
+        Ok(Outcome::Print(format!("{} has no dependencies", name)))
+    } else {
+        Ok(Outcome::Print(deps.join(", ")))
+    }
+}
+
+fn clear(args: &str, env: &mut Env) -> Parsed<Outcome> {
+    for name in args.split_whitespace() {
+        env.clear(name)?;
+    }
+    Ok(Outcome::Done)
+}
+
+fn history(_: &str, env: &mut Env) -> Parsed<Outcome> {
+    let lines: Vec<_> = env
+        .history()
+        .enumerate()
+        .map(|(i, (input, value))| format!("[{}] = {}  # {}", i + 1, value, input))
+        .collect();
+    Ok(Outcome::Print(lines.join("\n")))
+}
+
+fn rates(args: &str, env: &mut Env) -> Parsed<Outcome> {
+    if args.is_empty() {
+        let lines: Vec<_> = env
+            .rates
+            .iter()
+            .map(|(code, rate)| format!("{} = {}", code, Number::Rat(rate.clone()).try_into_int()))
+            .collect();
+        return Ok(Outcome::Print(lines.join("\n")));
+    }
+    env.rates.extend(Rates::load(Path::new(args))?);
+    Ok(Outcome::Done)
+}
+
+fn set(args: &str, env: &mut Env) -> Parsed<Outcome> {
+    match args.split_whitespace().collect::<Vec<_>>()[..] {
+        [] => {
+            let lines: Vec<_> = env
+                .settings
+                .entries()
+                .into_iter()
+                .map(|(key, value)| format!("{} = {}", key, value))
+                .collect();
+            Ok(Outcome::Print(lines.join("\n")))
+        }
+        [key] => Ok(Outcome::Print(env.settings.get(key)?)),
+        // The value can have spaces, e.g. :set bits 32 signed
+        [key, ..] => {
+            env.settings.set(key, args[key.len()..].trim())?;
+            Ok(Outcome::Done)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::interpreter::run;
+
+    struct Twice;
+
+    impl Command for Twice {
+        fn name(&self) -> &str {
+            "twice"
+        }
+        fn help(&self) -> &str {
+            "Double ans"
+        }
+        fn run(&self, _: &str, env: &mut Env) -> Parsed<Outcome> {
+            let result = run("2ans", env)?;
+            Ok(Outcome::Print(result.to_string()))
+        }
+    }
+
+    #[test]
+    fn _builtin() {
+        let commands = Commands::builtin();
+        let mut env = Env::prelude();
+        run("x = 1/2", &mut env).unwrap();
+        run("pi = 3", &mut env).unwrap();
+        let mut print = |line| commands.run(line, &mut env);
+        assert_eq!(
+            print(":vars"),
+            Ok(Outcome::Print("pi = 3\nx = 1/2".to_owned()))
+        );
+        assert_eq!(print(":type x"), Ok(Outcome::Print("rational".to_owned())));
+        assert_eq!(print(":clear pi x"), Ok(Outcome::Done));
+        assert_eq!(print(":vars"), Ok(Outcome::Print("".to_owned())));
+        assert_eq!(print(":type pi"), Ok(Outcome::Print("float".to_owned())));
+        let date = print(":type 2026-10-17 + 1 day");
+        assert_eq!(date, Ok(Outcome::Print("date".to_owned())));
+        assert!(print(":clear sin").is_err());
+        assert_eq!(print(":set suppress on"), Ok(Outcome::Done));
+        assert_eq!(print(":set suppress"), Ok(Outcome::Print("on".to_owned())));
+        assert_eq!(print(":quit"), Ok(Outcome::Quit));
+        assert!(print(":frobnicate").is_err());
+    }
+
+    #[test]
+    fn _help() {
+        let commands = Commands::builtin();
+        let mut env = Env::prelude();
+        assert_eq!(
+            commands.run(":help -", &mut env),
+            Ok(Outcome::Print(
+                "-x\nNegation\nAlso called −\n  -(2-5) = 3\n\nx - y\nSubtraction\nAlso called −\n  1 - 0.25 = 3/4"
+                    .to_owned()
+            ))
+        );
+        assert_eq!(
+            commands.run(":help :clear", &mut env),
+            Ok(Outcome::Print(
+                ":clear NAME\nRemove a variable, or restore it if it was built in".to_owned()
+            ))
+        );
+        let Ok(Outcome::Print(funcs)) = commands.run(":funcs", &mut env) else {
+            panic!()
+        };
+        assert!(funcs.starts_with("Arithmetic\n  +x"));
+        assert!(funcs.contains("\n  acos(x), arccos(x)    Inverse cosine, in radians\n"));
+        assert_eq!(
+            commands.run(":help arctan", &mut env),
+            Ok(Outcome::Print(
+                "arctan(x)\nInverse tangent, in radians\nAlso called atan\n  \
+                 atan 0 = 0\n  atan 1 = 0.7853981633974483"
+                    .to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn _shorthands() {
+        let mut env = Env::prelude();
+        run("x = 2", &mut env).unwrap();
+        let line = |text| command_line(text, &env);
+        assert_eq!(line("help sin"), Some(":help sin".to_owned()));
+        assert_eq!(line("?sin"), Some(":help sin".to_owned()));
+        assert_eq!(line("help"), Some(":help ".to_owned()));
+        assert_eq!(line("deps x"), Some(":deps x".to_owned()));
+        assert_eq!(line("deps y"), None);
+        assert_eq!(line("help x + 1"), None);
+        run("help = 3", &mut env).unwrap();
+        assert_eq!(command_line("help x", &env), None);
+    }
+
+    #[test]
+    fn _register() {
+        let mut commands = Commands::builtin();
+        commands.register(Box::new(Twice));
+        let mut env = Env::prelude();
+        env.record("21", Number::from(21));
+        assert_eq!(
+            commands.run(":twice", &mut env),
+            Ok(Outcome::Print("42".to_owned()))
+        );
+        let Ok(Outcome::Print(help)) = commands.run(":help", &mut env) else {
+            panic!()
+        };
+        assert!(help.ends_with(":twice                Double ans"));
+    }
+}
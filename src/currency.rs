@@ -0,0 +1,205 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use num::rational::Ratio;
+use num::{BigInt, Num, Signed, Zero};
+
+use crate::number::Number;
+use crate::parser::Parsed;
+use crate::value::Value;
+
+// The rate files read on start, from the current directory
+pub const DEFAULT_FILES: [&str; 2] = [".cwim_rates.toml", ".cwim_rates.csv"];
+
+// Exchange rates, as what one unit of each currency is worth in a common
+// unit, e.g. USD = 1 and EUR = 1.08
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Rates {
+    values: BTreeMap<String, Ratio<BigInt>>,
+}
+
+// Currency codes are 3 capital letters, e.g. USD
+pub fn is_code(name: &str) -> bool {
+    name.len() == 3 && name.bytes().all(|b| b.is_ascii_uppercase())
+}
+
+// The currencies in use from ISO 4217, sorted
+const ISO_4217: [&str; 156] = [
+    "AED", "AFN", "ALL", "AMD", "ANG", "AOA", "ARS", "AUD", "AWG", "AZN", "BAM", "BBD", "BDT",
+    "BGN", "BHD", "BIF", "BMD", "BND", "BOB", "BRL", "BSD", "BTN", "BWP", "BYN", "BZD", "CAD",
+    "CDF", "CHF", "CLP", "CNY", "COP", "CRC", "CUP", "CVE", "CZK", "DJF", "DKK", "DOP", "DZD",
+    "EGP", "ERN", "ETB", "EUR", "FJD", "FKP", "GBP", "GEL", "GHS", "GIP", "GMD", "GNF", "GTQ",
+    "GYD", "HKD", "HNL", "HTG", "HUF", "IDR", "ILS", "INR", "IQD", "IRR", "ISK", "JMD", "JOD",
+    "JPY", "KES", "KGS", "KHR", "KMF", "KPW", "KRW", "KWD", "KYD", "KZT", "LAK", "LBP", "LKR",
+    "LRD", "LSL", "LYD", "MAD", "MDL", "MGA", "MKD", "MMK", "MNT", "MOP", "MRU", "MUR", "MVR",
+    "MWK", "MXN", "MYR", "MZN", "NAD", "NGN", "NIO", "NOK", "NPR", "NZD", "OMR", "PAB", "PEN",
+    "PGK", "PHP", "PKR", "PLN", "PYG", "QAR", "RON", "RSD", "RUB", "RWF", "SAR", "SBD", "SCR",
+    "SDG", "SEK", "SGD", "SHP", "SLE", "SOS", "SRD", "SSP", "STN", "SVC", "SYP", "SZL", "THB",
+    "TJS", "TMT", "TND", "TOP", "TRY", "TTD", "TWD", "TZS", "UAH", "UGX", "USD", "UYU", "UZS",
+    "VES", "VND", "VUV", "WST", "XAF", "XCD", "XCG", "XOF", "XPF", "YER", "ZAR", "ZMW", "ZWG",
+];
+
+// Unbound currency codes stand for one unit of the currency, e.g. in 120 USD,
+// if they're from ISO 4217 or have an exchange rate, so that typos aren't money
+pub fn unit(name: &str, rates: &Rates) -> Option<Value> {
+    let known = ISO_4217.binary_search(&name).is_ok() || rates.values.contains_key(name);
+    known.then(|| Value::Money(Ratio::from_integer(1.into()), name.to_owned()))
+}
+
+// The digits after the point money is shown with, from ISO 4217
+pub fn decimals(code: &str) -> usize {
+    match code {
+        "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF" | "UGX"
+        | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+        "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+        _ => 2,
+    }
+}
+
+// An amount rounded half away from zero to the decimals of its currency,
+// e.g. 164.27 GBP
+pub fn format(amount: &Ratio<BigInt>, code: &str) -> String {
+    let scale = BigInt::from(10).pow(decimals(code) as u32);
+    let cents = (amount * &scale).round().to_integer();
+    let sign = if cents.is_negative() { "-" } else { "" };
+    let cents = cents.abs();
+    let whole = &cents / &scale;
+    match decimals(code) {
+        0 => format!("{}{} {}", sign, whole, code),
+        width => {
+            let fraction = (&cents % &scale).to_string();
+            format!("{}{}.{:0>width$} {}", sign, whole, fraction, code)
+        }
+    }
+}
+
+fn rate(text: &str) -> Parsed<Ratio<BigInt>> {
+    let rate = match Number::from_str_radix(text.trim(), 10) {
+        Ok(Number::Int(n)) => Ratio::from_integer(n),
+        Ok(Number::Rat(r)) => r,
+        _ => return Err(format!("'{}' is not a rate", text.trim())),
+    };
+    match rate.is_positive() {
+        true => Ok(rate),
+        false => Err(format!("'{}' is not a positive rate", text.trim())),
+    }
+}
+
+impl Rates {
+    pub const EMPTY: Rates = Rates {
+        values: BTreeMap::new(),
+    };
+
+    // Reads `.toml` files as `CODE = rate` lines, with an optional
+    // `base = "USD"`, and anything else as `CODE,rate` lines
+    pub fn load(path: &Path) -> Parsed<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Can't read '{}': {}", path.display(), e))?;
+        let rates = match path.extension().and_then(|it| it.to_str()) {
+            Some("toml") => Self::from_toml(&text),
+            _ => Self::from_csv(&text),
+        };
+        rates.map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    // `CODE,rate` lines, below an optional header such as `currency,rate`
+    pub fn from_csv(text: &str) -> Parsed<Self> {
+        let mut rates = Self::default();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parsed = match line.split(',').collect::<Vec<_>>()[..] {
+                [_, value] if i == 0 && rate(value).is_err() => continue,
+                [code, value] => rate(value).and_then(|it| rates.insert(code.trim(), it)),
+                _ => Err(format!("expected 'CODE,rate', found '{}'", line)),
+            };
+            parsed.map_err(|e| format!("line {}: {}", i + 1, e))?;
+        }
+        Ok(rates)
+    }
+
+    // `CODE = rate` lines, sections such as `[rates]` being ignored
+    pub fn from_toml(text: &str) -> Parsed<Self> {
+        let mut rates = Self::default();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() || line.starts_with('[') {
+                continue;
+            }
+            let parsed = match line.split_once('=') {
+                Some((key, code)) if key.trim() == "base" => {
+                    let code = code.trim().trim_matches('"');
+                    rates.insert(code, Ratio::from_integer(1.into()))
+                }
+                Some((code, value)) => rate(value).and_then(|it| rates.insert(code.trim(), it)),
+                None => Err(format!("expected 'CODE = rate', found '{}'", line)),
+            };
+            parsed.map_err(|e| format!("line {}: {}", i + 1, e))?;
+        }
+        Ok(rates)
+    }
+
+    fn insert(&mut self, code: &str, rate: Ratio<BigInt>) -> Parsed<()> {
+        if !is_code(code) {
+            return Err(format!("'{}' is not a currency code", code));
+        }
+        self.values.insert(code.to_owned(), rate);
+        Ok(())
+    }
+
+    // Adds the rates of `other`, replacing those of the same currencies
+    pub fn extend(&mut self, other: Rates) {
+        self.values.extend(other.values);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Ratio<BigInt>)> {
+        self.values.iter().map(|(code, rate)| (code.as_str(), rate))
+    }
+
+    // `amount` of `from` in `to`
+    pub fn convert(&self, amount: Ratio<BigInt>, from: &str, to: &str) -> Parsed<Ratio<BigInt>> {
+        if from == to || amount.is_zero() {
+            return Ok(amount);
+        }
+        match (self.values.get(from), self.values.get(to)) {
+            (Some(x), Some(y)) => Ok(amount * x / y),
+            _ => Err(format!(
+                "No exchange rate between {} and {}, see :rates",
+                from, to
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ratio(n: i64, d: i64) -> Ratio<BigInt> {
+        Ratio::new(n.into(), d.into())
+    }
+
+    #[test]
+    fn _parse() {
+        let csv = Rates::from_csv("currency,rate\nUSD,1\nEUR,1.08\n").unwrap();
+        let toml = Rates::from_toml("base = \"USD\"\n[rates]\nEUR = 1.08 # 2026-10-17\n").unwrap();
+        assert_eq!(csv, toml);
+        assert_eq!(csv.convert(ratio(100, 1), "EUR", "USD"), Ok(ratio(108, 1)));
+        assert!(csv.convert(ratio(1, 1), "EUR", "GBP").is_err());
+        assert_eq!(
+            Rates::from_csv("USD,1\nEUR,lots"),
+            Err("line 2: 'lots' is not a rate".to_owned())
+        );
+        assert!(Rates::from_toml("usd = 1").is_err());
+    }
+
+    #[test]
+    fn _format() {
+        assert_eq!(format(&ratio(1, 3), "USD"), "0.33 USD");
+        assert_eq!(format(&ratio(-5, 2), "EUR"), "-2.50 EUR");
+        assert_eq!(format(&ratio(1001, 2), "JPY"), "501 JPY");
+        assert_eq!(format(&ratio(1, 1000), "KWD"), "0.001 KWD");
+    }
+}
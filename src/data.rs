@@ -0,0 +1,147 @@
+use std::path::Path;
+
+use num::Num;
+
+use crate::number::Number;
+use crate::parser::Parsed;
+
+// Which column of a file to read, by its name in the header or its position
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    Name(String),
+    // Starting from 1
+    Position(usize),
+    // The only column of the file
+    Only,
+}
+
+// The numbers in a column of a CSV file, e.g. load("measurements.csv", "temp")
+pub fn load(path: &Path, column: &Column) -> Parsed<Vec<Number>> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Can't read '{}': {}", path.display(), e))?;
+    from_csv(&text, column).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// Decimals are read exactly, e.g. 20.1 is 201/10. The sign is read here,
+// as from_str_radix only expects digits.
+fn number(text: &str) -> Option<Number> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    if !digits.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+    let n = Number::from_str_radix(digits, 10).ok()?;
+    Some(if negative { -n } else { n })
+}
+
+// The fields of a line separated by `,`, or `;` or tabs if there are none,
+// without the quotes around them
+fn fields(line: &str) -> Vec<&str> {
+    let separator = [',', ';', '\t']
+        .into_iter()
+        .find(|it| line.contains(*it))
+        .unwrap_or(',');
+    line.split(separator)
+        .map(|it| it.trim().trim_matches('"').trim())
+        .collect()
+}
+
+// The first line is a header when some of its fields aren't numbers. Empty
+// cells are skipped, as missing data.
+pub fn from_csv(text: &str, column: &Column) -> Parsed<Vec<Number>> {
+    let mut rows = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|(i, line)| (i + 1, fields(line)))
+        .peekable();
+    let header = match rows.peek() {
+        Some((_, first)) if first.iter().any(|it| number(it).is_none()) => rows.next(),
+        Some(_) => None,
+        None => return Err("no data".to_owned()),
+    };
+    let index = match (column, &header) {
+        (Column::Name(name), Some((_, names))) => names
+            .iter()
+            .position(|it| it == name)
+            .ok_or_else(|| format!("no column '{}', found {}", name, names.join(", ")))?,
+        (Column::Name(name), None) => {
+            return Err(format!("no column '{}', as there is no header", name))
+        }
+        (Column::Position(0), _) => return Err("columns start at 1".to_owned()),
+        (Column::Position(n), _) => n - 1,
+        (Column::Only, _) => match rows.peek().or(header.as_ref()) {
+            Some((_, fields)) if fields.len() > 1 => {
+                return Err(format!(
+                    "found {} columns, name the one to load",
+                    fields.len()
+                ))
+            }
+            _ => 0,
+        },
+    };
+    let name = header
+        .as_ref()
+        .and_then(|(_, names)| names.get(index).copied())
+        .unwrap_or_default();
+    let mut numbers = vec![];
+    for (row, fields) in rows {
+        match fields.get(index).copied().unwrap_or_default() {
+            "" => continue,
+            field => numbers.push(number(field).ok_or_else(|| match name {
+                "" => format!("row {}: '{}' is not a number", row, field),
+                name => format!("row {}: '{}' in {} is not a number", row, field, name),
+            })?),
+        }
+    }
+    Ok(numbers)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn numbers(xs: &[(i64, i64)]) -> Vec<Number> {
+        let ratio = |(n, d): &(i64, i64)| Number::from(*n) / Number::from(*d);
+        xs.iter().map(ratio).collect()
+    }
+
+    #[test]
+    fn _from_csv() {
+        let csv = "time,temp\n0,20.5\n60,-1.25\n120,\n180,21\n";
+        let temp = Column::Name("temp".to_owned());
+        assert_eq!(
+            from_csv(csv, &temp),
+            Ok(numbers(&[(41, 2), (-5, 4), (21, 1)]))
+        );
+        assert_eq!(
+            from_csv(csv, &Column::Position(1)),
+            Ok(numbers(&[(0, 1), (60, 1), (120, 1), (180, 1)]))
+        );
+        assert_eq!(
+            from_csv("1\n2.5\n", &Column::Only),
+            Ok(numbers(&[(1, 1), (5, 2)]))
+        );
+        assert_eq!(
+            from_csv("\"a\";\"b\"\n1;2\n", &Column::Name("b".to_owned())),
+            Ok(numbers(&[(2, 1)]))
+        );
+    }
+
+    #[test]
+    fn _errors() {
+        let temp = Column::Name("temp".to_owned());
+        assert_eq!(
+            from_csv("time,temp\n0,20\n60,warm\n", &temp),
+            Err("row 3: 'warm' in temp is not a number".to_owned())
+        );
+        assert_eq!(
+            from_csv("time,temperature\n0,20\n", &temp),
+            Err("no column 'temp', found time, temperature".to_owned())
+        );
+        assert!(from_csv("1,2\n", &temp).is_err());
+        assert!(from_csv("1,2\n", &Column::Only).is_err());
+    }
+}
@@ -1,12 +1,23 @@
+use std::sync::Arc;
+
+use crate::currency::Rates;
 use crate::function::*;
 use crate::interpreter::Expr;
 use crate::number::Number;
-use crate::parser::Parsed;
+use crate::parser::{self, Parsed};
+use crate::pratt;
+use crate::s;
+use crate::settings::Settings;
+use crate::time::{self, Clock};
+use crate::token::TokenType;
+use crate::value::Value;
 
 #[derive(Debug)]
 pub enum Variable<'f> {
     Function(Functions<'f>),
-    Value(Number),
+    Value(Value),
+    // The text of an expression, re-evaluated every time the name is used
+    Lazy(String),
 }
 
 #[derive(Debug)]
@@ -28,9 +39,33 @@ impl<'f> Functions<'f> {
             unary: None,
         }
     }
+    // The function to call with `n` arguments, e.g. in `f(x, y)`
+    pub fn with_args(&self, n: usize) -> Option<Function<'f>> {
+        match (&self.unary, &self.binary) {
+            (Some(f), _) if f.arity == 0 || n == 1 => Some(f.clone()),
+            (_, Some(f)) if n == 2 => Some(f.clone()),
+            _ => None,
+        }
+    }
+    pub fn get(&self, arity: u8) -> Option<Function<'f>> {
+        match arity {
+            0 | 1 => self.unary.clone(),
+            2 => self.binary.clone(),
+            _ => None,
+        }
+    }
 }
 pub struct Env<'f> {
     inner: std::collections::HashMap<String, Variable<'f>>,
+    // Every result with the input that produced it, referenced as $1, $2, ..., _ and __
+    history: Vec<(String, Variable<'f>)>,
+    pub settings: Settings,
+    // Whether the last value didn't fit in the word set with `:bits`
+    pub overflow: bool,
+    // What `now` is
+    clock: Clock,
+    // What money is converted with, e.g. in 120 USD + 80 EUR
+    pub rates: Rates,
 }
 
 fn binary<'f>(symbol: &'static str, f: Function<'f>) -> (String, Variable<'f>) {
@@ -42,7 +77,7 @@ fn unary<'f>(symbol: &'static str, f: Function<'f>) -> (String, Variable<'f>) {
 }
 
 fn value(symbol: &'static str, n: Number) -> (String, Variable<'static>) {
-    (symbol.to_owned(), Variable::Value(n))
+    (symbol.to_owned(), Variable::Value(Value::Number(n)))
 }
 
 impl<'f> Env<'f> {
@@ -64,12 +99,56 @@ impl<'f> Env<'f> {
                     }),
                 ),
                 binary("*", MUL),
+                binary("×", MUL),
+                binary("·", MUL),
                 binary("/", DIV),
+                binary("÷", DIV),
+                (
+                    "−".to_owned(),
+                    Variable::Function(Functions {
+                        unary: Some(NEG),
+                        binary: Some(SUB),
+                    }),
+                ),
+                unary("√", SQRT),
+                unary("∛", CBRT),
+                value("π", Number::Flt(std::f64::consts::PI)),
                 binary("^", POW),
                 binary("%", REM),
                 unary("sqrt", SQRT),
                 unary("cbrt", CBRT),
                 value("pi", Number::Flt(std::f64::consts::PI)),
+                value("e", Number::Flt(std::f64::consts::E)),
+                value("inf", Number::Flt(f64::INFINITY)),
+                unary("~", NOT),
+                binary("&", AND),
+                binary("|", OR),
+                binary("xor", XOR),
+                binary("<<", SHL),
+                binary(">>", SHR),
+                unary("popcount", POPCOUNT),
+                binary("in", IN),
+                unary("count", COUNT),
+                unary("sum", SUM),
+                unary("mean", MEAN),
+                unary("median", MEDIAN),
+                unary("mode", MODE),
+                unary("var", VAR),
+                unary("stdev", STDEV),
+                unary("min", MIN),
+                unary("max", MAX),
+                binary("quantile", QUANTILE),
+                unary("poly", POLY),
+                (
+                    "deriv".to_owned(),
+                    Variable::Function(Functions {
+                        unary: Some(DERIV),
+                        binary: Some(DERIV_BY),
+                    }),
+                ),
+                unary("degree", DEGREE),
+                unary("coeffs", COEFFS),
+                binary("gcd", GCD),
                 unary("cos", COS),
                 unary("sin", SIN),
                 unary("tan", TAN),
@@ -92,17 +171,273 @@ impl<'f> Env<'f> {
                 unary("arcsinh", ASINH),
                 unary("arctanh", ATANH),
             ]),
+            history: vec![],
+            settings: Settings::default(),
+            overflow: false,
+            clock: Arc::new(time::system_clock),
+            rates: Rates::default(),
         }
     }
 
-    pub fn get(&self, l: &str) -> Option<&Variable> {
-        self.inner.get(l)
+    // Makes `now` the time `clock` gives, in seconds since 1970-01-01 UTC
+    pub fn set_clock(&mut self, clock: impl Fn() -> Number + Send + Sync + 'static) {
+        self.clock = Arc::new(clock);
     }
 
-    pub fn find_value(&self, l: &str) -> Parsed<Expr> {
-        let var = self.inner.get(l);
+    pub fn now(&self) -> Value {
+        Value::Date((self.clock)())
+    }
+
+    pub fn get(&self, l: &str) -> Option<&Variable<'f>> {
+        self.inner.get(l).or_else(|| self.recall(l))
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.inner.keys().map(|it| it.as_str())
+    }
+
+    fn recall(&self, l: &str) -> Option<&Variable<'f>> {
+        let index = match l {
+            "_" => self.history.len().checked_sub(1)?,
+            "__" => self.history.len().checked_sub(2)?,
+            _ => l.strip_prefix('$')?.parse::<usize>().ok()?.checked_sub(1)?,
+        };
+        self.history.get(index).map(|(_, var)| var)
+    }
+
+    // Adds a result to the history and to `ans`, returning its number.
+    pub fn record(&mut self, input: &str, result: impl Into<Value>) -> usize {
+        let result = result.into();
+        self.assign("ans".to_owned(), result.clone());
+        self.history
+            .push((input.to_owned(), Variable::Value(result)));
+        self.history.len()
+    }
+
+    pub fn history(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.history.iter().filter_map(|(input, var)| match var {
+            Variable::Value(n) => Some((input.as_str(), n)),
+            _ => None,
+        })
+    }
+
+    // Values and definitions that weren't in the prelude, or that were changed since, by name.
+    pub fn user_variables(&self) -> Vec<(&str, &Variable<'f>)> {
+        let prelude = Env::prelude();
+        let mut result: Vec<_> = self
+            .inner
+            .iter()
+            .filter(|(name, var)| match (var, prelude.inner.get(*name)) {
+                // Functions can only come from the prelude
+                (Variable::Function(_), _) => false,
+                (Variable::Value(n), Some(Variable::Value(m))) => n != m,
+                _ => true,
+            })
+            .map(|(name, var)| (name.as_str(), var))
+            .collect();
+        result.sort_by_key(|(name, _)| *name);
+        result
+    }
+
+    pub fn functions(&self) -> Vec<(&str, &Functions<'f>)> {
+        let mut result: Vec<_> = self
+            .inner
+            .iter()
+            .filter_map(|(name, var)| match var {
+                Variable::Function(fs) => Some((name.as_str(), fs)),
+                _ => None,
+            })
+            .collect();
+        result.sort_by_key(|(name, _)| *name);
+        result
+    }
+
+    // Forgets every variable and result, but keeps the settings.
+    pub fn reset(&mut self) {
+        let prelude = Env::prelude();
+        self.inner = prelude.inner;
+        self.history.clear();
+    }
+
+    // Removes a variable or definition, or restores it if it was in the prelude.
+    pub fn clear(&mut self, name: &str) -> Parsed<()> {
+        match (self.inner.get(name), Env::prelude().inner.remove(name)) {
+            (Some(Variable::Function(_)), Some(_)) => {
+                Err(format!("Can't clear function '{}'", name))
+            }
+            (Some(_), Some(prelude)) => {
+                self.inner.insert(name.to_owned(), prelude);
+                Ok(())
+            }
+            (Some(_), None) => {
+                self.inner.remove(name);
+                Ok(())
+            }
+            (None, _) => Err(format!("Can't find '{}'", name)),
+        }
+    }
+
+    // Declares an operator defined in cwim, from e.g. `<+> 5 right (a, b) -> sqrt(a^2+b^2)`,
+    // the associativity being optional.
+    pub fn infix(&mut self, declaration: &str) -> Parsed<()> {
+        let usage = || {
+            format!(
+                "Expected e.g. '<+> 5 left (a, b) -> a+b', found '{}'",
+                declaration
+            )
+        };
+        let (head, rest) = declaration.split_once('(').ok_or_else(usage)?;
+        let (params, body) = rest.split_once(')').ok_or_else(usage)?;
+        let body = body.trim().strip_prefix("->").ok_or_else(usage)?;
+        let params: Vec<_> = params.split(',').map(str::trim).collect();
+        let (symbol, priority, assoc) = match head.split_whitespace().collect::<Vec<_>>()[..] {
+            [symbol, priority] => (symbol, priority, Assoc::Left),
+            [symbol, priority, "left"] => (symbol, priority, Assoc::Left),
+            [symbol, priority, "right"] => (symbol, priority, Assoc::Right),
+            _ => return Err(usage()),
+        };
+        let priority = priority
+            .parse()
+            .map_err(|_| format!("Expected a priority, found '{}'", priority))?;
+        match params[..] {
+            [a, b] => self.define_infix(symbol, priority, assoc, [a, b], body.trim()),
+            _ => Err(format!(
+                "An operator takes 2 arguments, found {}",
+                params.len()
+            )),
+        }
+    }
+
+    // Adds an operator such as `a <+> b`, which evaluates `body` with `params`
+    // for its arguments.
+    pub fn define_infix(
+        &mut self,
+        symbol: &str,
+        priority: u16,
+        assoc: Assoc,
+        params: [&str; 2],
+        body: &str,
+    ) -> Parsed<()> {
+        if symbol.is_empty() || !symbol.chars().all(|c| parser::OPERATORS.contains(c)) {
+            return Err(format!(
+                "Operators are made of {}, found '{}'",
+                parser::OPERATORS,
+                symbol
+            ));
+        }
+        // Built in operators can't be redefined, except for the bit operations,
+        // which an operator of one's own replaces, e.g. `<<`
+        match Env::prelude().inner.get(symbol) {
+            Some(Variable::Function(fs))
+                if [&fs.unary, &fs.binary]
+                    .into_iter()
+                    .flatten()
+                    .all(|f| f.doc.category == Category::Bitwise) => {}
+            Some(_) => return Err(format!("Can't redefine '{}'", symbol)),
+            None => {}
+        }
+        for param in params {
+            if !parser::is_name(param, self)
+                || matches!(self.get(param), Some(Variable::Function(_)))
+            {
+                return Err(format!("Expected a parameter name, found '{}'", param));
+            }
+        }
+        let definition = Definition {
+            params: params.map(str::to_owned).to_vec(),
+            body: body.to_owned(),
+        };
+        let f =
+            Function::native(symbol, priority, F::Defined(Arc::new(definition))).with_assoc(assoc);
+        let previous = self.inner.remove(symbol);
+        self.register(f);
+        let mut operators = vec![];
+        let checked =
+            self.collect_operators(body, &mut operators).and_then(|_| {
+                match parser::stmt(body, self)? {
+                    parser::Stmt::Expr(_) if operators.iter().any(|it| it == symbol) => Err(
+                        format!("Cyclic definition: '{}' would depend on itself", symbol),
+                    ),
+                    parser::Stmt::Expr(_) => Ok(()),
+                    _ => Err(format!("Expected an expression, found '{}'", body)),
+                }
+            });
+        if checked.is_err() {
+            self.inner.remove(symbol);
+            if let Some(previous) = previous {
+                self.inner.insert(symbol.to_owned(), previous);
+            }
+        }
+        checked
+    }
+
+    // The operators defined in cwim used by `body`, and by theirs
+    fn collect_operators(&self, body: &str, operators: &mut Vec<String>) -> Parsed<()> {
+        for token in parser::stmt(body, self)?.rhs() {
+            if token.ttype != TokenType::Symbol || operators.iter().any(|it| it == token.lexeme) {
+                continue;
+            }
+            if let Some(definition) = self.definition(token.lexeme) {
+                operators.push(token.lexeme.to_owned());
+                self.collect_operators(&definition.body, operators)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn definition(&self, symbol: &str) -> Option<&Definition> {
+        match self.inner.get(symbol) {
+            Some(Variable::Function(Functions {
+                binary:
+                    Some(Function {
+                        f: F::Defined(definition),
+                        ..
+                    }),
+                ..
+            })) => Some(definition),
+            _ => None,
+        }
+    }
+
+    // Every operator defined in cwim, as declared with `infix`
+    pub fn operators(&self) -> Vec<String> {
+        let mut result: Vec<_> = self
+            .functions()
+            .into_iter()
+            .filter_map(|(name, fs)| {
+                let f = fs.binary.as_ref()?;
+                let definition = self.definition(name)?;
+                let assoc = match f.assoc {
+                    Assoc::Left => "left",
+                    Assoc::Right => "right",
+                };
+                Some(format!(
+                    "{} {} {} ({}) -> {}",
+                    name,
+                    f.priority,
+                    assoc,
+                    definition.params.join(", "),
+                    definition.body
+                ))
+            })
+            .collect();
+        result.sort();
+        result
+    }
+
+    pub fn find_value(&self, l: &str) -> Parsed<Expr<'_>> {
+        self.value(l)?.into_number().map(Expr::Literal)
+    }
+
+    // The number, date or duration a variable or definition stands for
+    pub fn value(&self, l: &str) -> Parsed<Value> {
+        let var = self.get(l);
         match var {
-            Some(Variable::Value(n)) => Ok(Expr::Literal(n.clone())),
+            Some(Variable::Value(v)) => Ok(v.clone()),
+            Some(Variable::Lazy(body)) => {
+                let mut tokens = parser::stmt(body, self)?.rhs().clone();
+                s::eval_value(&pratt::expr(&mut tokens, self)?)
+            }
             Some(Variable::Function(_)) => Err(format!(
                 "Expected value '{}', found function with that name.",
                 l
@@ -111,29 +446,115 @@ impl<'f> Env<'f> {
         }
     }
 
-    pub fn find_unary(&self, l: &str) -> Parsed<Function> {
+    pub fn find_unary(&self, l: &str) -> Parsed<Function<'f>> {
         let var = self.inner.get(l);
         match var {
             Some(Variable::Function(Functions {
                 binary: _,
                 unary: Some(unary),
-            })) => Ok(*unary),
+            })) => Ok(unary.clone()),
             _ => Err(format!("Can't find '{}'", l)),
         }
     }
 
-    pub fn find_binary(&self, l: &str) -> Parsed<Function> {
+    pub fn find_binary(&self, l: &str) -> Parsed<Function<'f>> {
         let var = self.inner.get(l);
         match var {
             Some(Variable::Function(Functions {
                 unary: _,
                 binary: Some(binary),
-            })) => Ok(*binary),
+            })) => Ok(binary.clone()),
             _ => Err(format!("Can't find '{}'", l)),
         }
     }
 
-    pub fn assign(&mut self, lhs: String, rhs: &Number) -> Option<Variable> {
-        self.inner.insert(lhs, Variable::Value(rhs.clone()))
+    // Adds a function, next to any other one with the same name and a different arity.
+    pub fn register(&mut self, f: Function<'f>) {
+        let name = f.name.to_string();
+        let mut fs = match self.inner.remove(&name) {
+            Some(Variable::Function(fs)) => fs,
+            _ => Functions {
+                unary: None,
+                binary: None,
+            },
+        };
+        match f.arity {
+            2 => fs.binary = Some(f),
+            _ => fs.unary = Some(f),
+        }
+        self.inner.insert(name, Variable::Function(fs));
+    }
+
+    // Adds a function written before its argument, e.g. `f x` or `f(x)`. The priority
+    // is on the same scale as the built in ones: 4 for `+` and named functions,
+    // 6 for `*` and 7 for `^`.
+    pub fn register_unary(&mut self, name: &str, priority: u16, f: Arc<UnaryFn>) {
+        self.register(Function::native(name, priority, F::NativeUnary(f)));
+    }
+
+    // Adds an operator written between its arguments if `name` is a symbol, e.g. `x <> y`,
+    // or a function of two arguments, e.g. `f(x, y)`
+    pub fn register_binary(&mut self, name: &str, priority: u16, f: Arc<BinaryFn>) {
+        self.register(Function::native(name, priority, F::NativeBinary(f)));
+    }
+
+    // Adds a function of any number of arguments, e.g. `f(x, y, z)`
+    pub fn register_variadic(&mut self, name: &str, priority: u16, f: Arc<VariadicFn>) {
+        self.register(Function::native(name, priority, F::NativeVariadic(f)));
+    }
+
+    pub fn assign(&mut self, lhs: String, rhs: impl Into<Value>) -> Option<Variable<'_>> {
+        self.inner.insert(lhs, Variable::Value(rhs.into()))
+    }
+
+    pub fn define(&mut self, name: &str, body: &str) -> Parsed<Option<Variable<'_>>> {
+        if let Some(Variable::Function(_)) = self.inner.get(name) {
+            return Err(format!("Can't redefine function '{}'", name));
+        }
+        if !matches!(parser::stmt(body, self)?, parser::Stmt::Expr(_)) {
+            return Err(format!(
+                "Expected an expression for '{}', found '{}'",
+                name, body
+            ));
+        }
+        let mut deps = vec![];
+        self.collect_deps(body, &mut deps)?;
+        if deps.iter().any(|it| it == name) {
+            return Err(format!(
+                "Cyclic definition: '{}' would depend on itself",
+                name
+            ));
+        }
+        Ok(self
+            .inner
+            .insert(name.to_owned(), Variable::Lazy(body.to_owned())))
+    }
+
+    // Every name the definition of `name` uses, directly or through other definitions.
+    pub fn deps(&self, name: &str) -> Parsed<Vec<String>> {
+        let mut deps = vec![];
+        match self.inner.get(name) {
+            Some(Variable::Lazy(body)) => self.collect_deps(body, &mut deps)?,
+            Some(_) => {}
+            None => return Err(format!("Can't find '{}'", name)),
+        }
+        Ok(deps)
+    }
+
+    fn collect_deps(&self, body: &str, deps: &mut Vec<String>) -> Parsed<()> {
+        for token in parser::stmt(body, self)?.rhs() {
+            let is_function = matches!(self.inner.get(token.lexeme), Some(Variable::Function(_)));
+            if token.ttype != TokenType::Identifier
+                || is_function
+                || deps.iter().any(|it| it == token.lexeme)
+            {
+                continue;
+            }
+            deps.push(token.lexeme.to_owned());
+            if let Some(Variable::Lazy(inner)) = self.inner.get(token.lexeme) {
+                self.collect_deps(inner, deps)?;
+            }
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,29 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CwimError {
+    // The input can't be parsed, or its value can't be found
+    Invalid(String),
+    // A name with no value, e.g. `x` in `x + 1` with no binding for `x`
+    Unbound(String),
+    // A definition stored without a value, e.g. `area := w*h` with no value for `w`
+    Pending(String),
+}
+
+impl fmt::Display for CwimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CwimError::Invalid(msg) => write!(f, "{}", msg),
+            CwimError::Unbound(name) => write!(f, "No value for '{}'", name),
+            CwimError::Pending(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for CwimError {}
+
+impl From<String> for CwimError {
+    fn from(msg: String) -> Self {
+        CwimError::Invalid(msg)
+    }
+}
@@ -1,38 +1,246 @@
+use std::borrow::Cow;
+use std::fmt;
 use std::ops::Neg;
+use std::sync::Arc;
 
 use num::pow::Pow;
 
+use crate::error::CwimError;
 use crate::number::Number;
+use crate::parser::Parsed;
+use crate::stats;
+use crate::value::{self, Value};
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct Function<'f> {
-    pub name: &'f str,
+    pub name: Cow<'f, str>,
+    // 0 for functions taking any number of arguments
     pub arity: u8,
     pub f: F,
     pub priority: u16,
+    pub assoc: Assoc,
+    pub doc: Doc<'f>,
 }
 
-#[derive(Debug, Clone, Copy)]
+// Which side of `a op b op c` is grouped first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assoc {
+    Left,
+    Right,
+}
+
+// An operator defined in cwim itself, e.g. `:infix <+> 5 (a, b) -> sqrt(a^2+b^2)`
+#[derive(Debug)]
+pub struct Definition {
+    pub params: Vec<String>,
+    pub body: String,
+}
+
+#[derive(Clone)]
 pub enum F {
     Binary(fn(Number, Number) -> Number),
+    // Values side by side, e.g. 2x, applied like a binary function
+    Implied(fn(Number, Number) -> Number),
     Nary(fn(Number) -> Number),
+    // Statistics, given every number of their arguments with lists spread out
+    Stat(fn(&[Number]) -> Number),
+    // Statistics of the numbers before the last argument, e.g. quantile(xs, q)
+    StatWith(fn(&[Number], Number) -> Number),
+    // Closures registered by programs using cwim, which take their arguments in order
+    NativeUnary(Arc<UnaryFn>),
+    NativeBinary(Arc<BinaryFn>),
+    NativeVariadic(Arc<VariadicFn>),
+    Defined(Arc<Definition>),
+    // Functions of polynomials, with unknowns in their arguments as variables
+    Polynomial(fn(&[Value]) -> Parsed<Value>),
+}
+
+pub type UnaryFn = dyn Fn(Number) -> Result<Number, CwimError> + Send + Sync;
+pub type BinaryFn = dyn Fn(Number, Number) -> Result<Number, CwimError> + Send + Sync;
+pub type VariadicFn = dyn Fn(&[Number]) -> Result<Number, CwimError> + Send + Sync;
+
+impl fmt::Debug for F {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            F::Binary(g) => write!(f, "Binary({:p})", g),
+            F::Implied(g) => write!(f, "Implied({:p})", g),
+            F::Nary(g) => write!(f, "Nary({:p})", g),
+            F::Stat(g) => write!(f, "Stat({:p})", g),
+            F::StatWith(g) => write!(f, "StatWith({:p})", g),
+            F::NativeUnary(g) => write!(f, "NativeUnary({:p})", Arc::as_ptr(g)),
+            F::NativeBinary(g) => write!(f, "NativeBinary({:p})", Arc::as_ptr(g)),
+            F::NativeVariadic(g) => write!(f, "NativeVariadic({:p})", Arc::as_ptr(g)),
+            F::Defined(d) => write!(f, "Defined({:?})", d),
+            F::Polynomial(g) => write!(f, "Polynomial({:p})", g),
+        }
+    }
+}
+
+// What `:funcs` groups functions by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Category {
+    Arithmetic,
+    Roots,
+    Exponential,
+    Trigonometric,
+    Hyperbolic,
+    Bitwise,
+    Statistics,
+    Polynomials,
+    Other,
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+// Documentation shown by `:help`
+#[derive(Debug, Clone, Copy)]
+pub struct Doc<'f> {
+    pub category: Category,
+    pub summary: &'f str,
+    pub args: &'f [&'f str],
+    // Where it's defined, if not for every number
+    pub domain: &'f str,
+    // Inputs with the values they print, checked by the tests
+    pub examples: &'f [(&'f str, &'f str)],
+}
+
+impl<'f> Doc<'f> {
+    pub const fn new(category: Category, summary: &'f str) -> Self {
+        Self {
+            category,
+            summary,
+            args: &[],
+            domain: "",
+            examples: &[],
+        }
+    }
+    pub const fn args(self, args: &'f [&'f str]) -> Self {
+        Self { args, ..self }
+    }
+    pub const fn domain(self, domain: &'f str) -> Self {
+        Self { domain, ..self }
+    }
+    pub const fn examples(self, examples: &'f [(&'f str, &'f str)]) -> Self {
+        Self { examples, ..self }
+    }
 }
 
 impl<'f> Function<'f> {
-    const fn unary(name: &'f str, priority: u16, f: fn(Number) -> Number) -> Self {
+    const fn unary(name: &'f str, priority: u16, doc: Doc<'f>, f: fn(Number) -> Number) -> Self {
         Self {
-            name,
+            name: Cow::Borrowed(name),
             arity: 1,
             f: F::Nary(f),
             priority,
+            assoc: Assoc::Left,
+            doc,
+        }
+    }
+    const fn binary(
+        name: &'f str,
+        priority: u16,
+        doc: Doc<'f>,
+        f: fn(Number, Number) -> Number,
+    ) -> Self {
+        Self {
+            name: Cow::Borrowed(name),
+            arity: 2,
+            f: F::Binary(f),
+            priority,
+            assoc: Assoc::Left,
+            doc,
+        }
+    }
+    // A function of any number of arguments or lists, e.g. sum(xs, 4)
+    const fn stat(name: &'f str, doc: Doc<'f>, f: fn(&[Number]) -> Number) -> Self {
+        Self {
+            name: Cow::Borrowed(name),
+            arity: 0,
+            f: F::Stat(f),
+            priority: 4,
+            assoc: Assoc::Left,
+            doc,
         }
     }
-    const fn binary(name: &'f str, priority: u16, f: fn(Number, Number) -> Number) -> Self {
+    // A function of polynomials, e.g. deriv(x^3)
+    const fn polynomial(
+        name: &'f str,
+        arity: u8,
+        doc: Doc<'f>,
+        f: fn(&[Value]) -> Parsed<Value>,
+    ) -> Self {
         Self {
-            name,
+            name: Cow::Borrowed(name),
+            arity,
+            f: F::Polynomial(f),
+            priority: 4,
+            assoc: Assoc::Left,
+            doc,
+        }
+    }
+    // A binary operator grouped from the right, e.g. a^b^c = a^(b^c)
+    const fn right(
+        name: &'f str,
+        priority: u16,
+        doc: Doc<'f>,
+        f: fn(Number, Number) -> Number,
+    ) -> Self {
+        Self {
+            name: Cow::Borrowed(name),
             arity: 2,
             f: F::Binary(f),
             priority,
+            assoc: Assoc::Right,
+            doc,
+        }
+    }
+
+    // A function registered by a program using cwim, or defined with `:infix`
+    pub fn native(name: &str, priority: u16, f: F) -> Self {
+        let (arity, args): (u8, &[&str]) = match f {
+            F::NativeBinary(_) | F::Binary(_) | F::Implied(_) | F::Defined(_) => (2, &["x", "y"]),
+            F::NativeVariadic(_) => (0, &["x"]),
+            _ => (1, &["x"]),
+        };
+        Self {
+            name: Cow::Owned(name.to_owned()),
+            arity,
+            f,
+            priority,
+            assoc: Assoc::Left,
+            doc: Doc::new(Category::Other, "").args(args),
+        }
+    }
+
+    pub fn with_assoc(self, assoc: Assoc) -> Self {
+        Self { assoc, ..self }
+    }
+
+    // Whether it's IMPLIED_MUL, which is equal to MUL as functions are compared by name
+    pub fn is_implied_mul(&self) -> bool {
+        *self == IMPLIED_MUL && matches!(self.f, F::Implied(_))
+    }
+
+    // How it's written, e.g. `sin(x)`, `-x` or `x + y`
+    pub fn usage(&self, name: &str) -> String {
+        let params = match &self.f {
+            F::Defined(definition) => definition.params.iter().map(String::as_str).collect(),
+            _ => self.doc.args.to_vec(),
+        };
+        let arg = |i: usize| params.get(i).copied().unwrap_or(["x", "y"][i.min(1)]);
+        let symbol = !name.starts_with(|c: char| c.is_alphabetic());
+        match (self.arity, symbol) {
+            (0, _) => format!("{}({}, ...)", name, arg(0)),
+            (1, true) => format!("{}{}", name, arg(0)),
+            (2, true) => format!("{} {} {}", arg(0), name, arg(1)),
+            _ => {
+                let args: Vec<_> = (0..self.arity as usize).map(arg).collect();
+                format!("{}({})", name, args.join(", "))
+            }
         }
     }
 }
@@ -51,30 +259,505 @@ impl<'f> std::fmt::Debug for Function<'f> {
     }
 }
 
-pub const SQRT: Function = Function::unary("sqrt", 4, |x| Number::Flt(x.f64_or_nan().sqrt()));
-pub const CBRT: Function = Function::unary("cbrt", 4, |x| Number::Flt(x.f64_or_nan().cbrt()));
-pub const COS: Function = Function::unary("cos", 4, |x| Number::Flt(x.f64_or_nan().cos()));
-pub const SIN: Function = Function::unary("sin", 4, |x| Number::Flt(x.f64_or_nan().sin()));
-pub const TAN: Function = Function::unary("tan", 4, |x| Number::Flt(x.f64_or_nan().tan()));
-pub const COSH: Function = Function::unary("cosh", 4, |x| Number::Flt(x.f64_or_nan().cosh()));
-pub const SINH: Function = Function::unary("sinh", 4, |x| Number::Flt(x.f64_or_nan().sinh()));
-pub const TANH: Function = Function::unary("tanh", 4, |x| Number::Flt(x.f64_or_nan().tanh()));
-pub const ACOS: Function = Function::unary("acos", 4, |x| Number::Flt(x.f64_or_nan().acos()));
-pub const ASIN: Function = Function::unary("asin", 4, |x| Number::Flt(x.f64_or_nan().asin()));
-pub const ATAN: Function = Function::unary("atan", 4, |x| Number::Flt(x.f64_or_nan().atan()));
-pub const ACOSH: Function = Function::unary("acosh", 4, |x| Number::Flt(x.f64_or_nan().acosh()));
-pub const ASINH: Function = Function::unary("asinh", 4, |x| Number::Flt(x.f64_or_nan().asinh()));
-pub const ATANH: Function = Function::unary("atanh", 4, |x| Number::Flt(x.f64_or_nan().atanh()));
-pub const EXP: Function = Function::unary("exp", 4, |x| Number::Flt(x.f64_or_nan().exp()));
-pub const LN: Function = Function::unary("ln", 4, |x| Number::Flt(x.f64_or_nan().ln()));
-pub const LOG: Function = Function::unary("log", 4, |x| Number::Flt(x.f64_or_nan().log10()));
-pub const NEG: Function = Function::unary("-", 6, |x| x.neg());
-pub const ID: Function = Function::unary("+", 6, |x| x);
-pub const ADD: Function = Function::binary("+", 4, |x, y| y + x);
-pub const SUB: Function = Function::binary("-", 4, |x, y| y - x);
-pub const MUL: Function = Function::binary("*", 6, |x, y| y * x);
-pub const DIV: Function = Function::binary("/", 6, |x, y| y / x);
+use Category::*;
+
+pub const SQRT: Function = Function::unary(
+    "sqrt",
+    4,
+    Doc::new(Roots, "Square root")
+        .args(&["x"])
+        .domain("x >= 0, NaN for negative numbers")
+        .examples(&[("sqrt 16", "4"), ("sqrt 2", "1.4142135623730951")]),
+    |x| Number::Flt(x.f64_or_nan().sqrt()),
+);
+pub const CBRT: Function = Function::unary(
+    "cbrt",
+    4,
+    Doc::new(Roots, "Cube root")
+        .args(&["x"])
+        .examples(&[("cbrt 27", "3"), ("cbrt(-8)", "-2")]),
+    |x| Number::Flt(x.f64_or_nan().cbrt()),
+);
+pub const COS: Function = Function::unary(
+    "cos",
+    4,
+    Doc::new(Trigonometric, "Cosine, in radians")
+        .args(&["x"])
+        .examples(&[("cos 0", "1"), ("cos pi", "-1")]),
+    |x| Number::Flt(x.f64_or_nan().cos()),
+);
+pub const SIN: Function = Function::unary(
+    "sin",
+    4,
+    Doc::new(Trigonometric, "Sine, in radians")
+        .args(&["x"])
+        .examples(&[("sin 0", "0"), ("sin(pi/2)", "1")]),
+    |x| Number::Flt(x.f64_or_nan().sin()),
+);
+pub const TAN: Function = Function::unary(
+    "tan",
+    4,
+    Doc::new(Trigonometric, "Tangent, in radians")
+        .args(&["x"])
+        .domain("x not an odd multiple of pi/2")
+        .examples(&[("tan 0", "0")]),
+    |x| Number::Flt(x.f64_or_nan().tan()),
+);
+pub const COSH: Function = Function::unary(
+    "cosh",
+    4,
+    Doc::new(Hyperbolic, "Hyperbolic cosine")
+        .args(&["x"])
+        .examples(&[("cosh 0", "1")]),
+    |x| Number::Flt(x.f64_or_nan().cosh()),
+);
+pub const SINH: Function = Function::unary(
+    "sinh",
+    4,
+    Doc::new(Hyperbolic, "Hyperbolic sine")
+        .args(&["x"])
+        .examples(&[("sinh 0", "0")]),
+    |x| Number::Flt(x.f64_or_nan().sinh()),
+);
+pub const TANH: Function = Function::unary(
+    "tanh",
+    4,
+    Doc::new(Hyperbolic, "Hyperbolic tangent")
+        .args(&["x"])
+        .examples(&[("tanh 0", "0")]),
+    |x| Number::Flt(x.f64_or_nan().tanh()),
+);
+pub const ACOS: Function = Function::unary(
+    "acos",
+    4,
+    Doc::new(Trigonometric, "Inverse cosine, in radians")
+        .args(&["x"])
+        .domain("-1 <= x <= 1, NaN outside")
+        .examples(&[("acos 1", "0"), ("acos 2", "NaN")]),
+    |x| Number::Flt(x.f64_or_nan().acos()),
+);
+pub const ASIN: Function = Function::unary(
+    "asin",
+    4,
+    Doc::new(Trigonometric, "Inverse sine, in radians")
+        .args(&["x"])
+        .domain("-1 <= x <= 1, NaN outside")
+        .examples(&[("asin 0", "0"), ("asin 1", "1.5707963267948966")]),
+    |x| Number::Flt(x.f64_or_nan().asin()),
+);
+pub const ATAN: Function = Function::unary(
+    "atan",
+    4,
+    Doc::new(Trigonometric, "Inverse tangent, in radians")
+        .args(&["x"])
+        .examples(&[("atan 0", "0"), ("atan 1", "0.7853981633974483")]),
+    |x| Number::Flt(x.f64_or_nan().atan()),
+);
+pub const ACOSH: Function = Function::unary(
+    "acosh",
+    4,
+    Doc::new(Hyperbolic, "Inverse hyperbolic cosine")
+        .args(&["x"])
+        .domain("x >= 1, NaN below")
+        .examples(&[("acosh 1", "0")]),
+    |x| Number::Flt(x.f64_or_nan().acosh()),
+);
+pub const ASINH: Function = Function::unary(
+    "asinh",
+    4,
+    Doc::new(Hyperbolic, "Inverse hyperbolic sine")
+        .args(&["x"])
+        .examples(&[("asinh 0", "0")]),
+    |x| Number::Flt(x.f64_or_nan().asinh()),
+);
+pub const ATANH: Function = Function::unary(
+    "atanh",
+    4,
+    Doc::new(Hyperbolic, "Inverse hyperbolic tangent")
+        .args(&["x"])
+        .domain("-1 < x < 1, infinite at -1 and 1")
+        .examples(&[("atanh 0", "0"), ("atanh 1", "inf")]),
+    |x| Number::Flt(x.f64_or_nan().atanh()),
+);
+pub const EXP: Function = Function::unary(
+    "exp",
+    4,
+    Doc::new(Exponential, "e to the power of x")
+        .args(&["x"])
+        .examples(&[("exp 0", "1"), ("exp 1", "2.718281828459045")]),
+    |x| Number::Flt(x.f64_or_nan().exp()),
+);
+pub const LN: Function = Function::unary(
+    "ln",
+    4,
+    Doc::new(Exponential, "Natural logarithm")
+        .args(&["x"])
+        .domain("x > 0, NaN for negative numbers")
+        .examples(&[("ln 1", "0"), ("ln exp 2", "2")]),
+    |x| Number::Flt(x.f64_or_nan().ln()),
+);
+pub const LOG: Function = Function::unary(
+    "log",
+    4,
+    Doc::new(Exponential, "Base 10 logarithm")
+        .args(&["x"])
+        .domain("x > 0, NaN for negative numbers")
+        .examples(&[("log 1000", "3")]),
+    |x| Number::Flt(x.f64_or_nan().log10()),
+);
+pub const NEG: Function = Function::unary(
+    "-",
+    6,
+    Doc::new(Arithmetic, "Negation")
+        .args(&["x"])
+        .examples(&[("-(2-5)", "3")]),
+    |x| x.neg(),
+);
+pub const ID: Function = Function::unary(
+    "+",
+    6,
+    Doc::new(Arithmetic, "Returns its argument unchanged")
+        .args(&["x"])
+        .examples(&[("+3", "3")]),
+    |x| x,
+);
+pub const ADD: Function = Function::binary(
+    "+",
+    4,
+    Doc::new(Arithmetic, "Addition")
+        .args(&["x", "y"])
+        .examples(&[("1/2 + 1/3", "5/6")]),
+    |x, y| y + x,
+);
+pub const SUB: Function = Function::binary(
+    "-",
+    4,
+    Doc::new(Arithmetic, "Subtraction")
+        .args(&["x", "y"])
+        .examples(&[("1 - 0.25", "3/4")]),
+    |x, y| y - x,
+);
+pub const MUL: Function = Function::binary(
+    "*",
+    6,
+    Doc::new(
+        Arithmetic,
+        "Multiplication, also written by putting values side by side",
+    )
+    .args(&["x", "y"])
+    .examples(&[("6*7", "42"), ("2(3+4)", "14")]),
+    |x, y| y * x,
+);
+// Values side by side, e.g. 2x, which multiply like * except for durations:
+// 3h 25min is 3h + 25min
+pub const IMPLIED_MUL: Function = Function {
+    name: Cow::Borrowed("*"),
+    arity: 2,
+    f: F::Implied(|x, y| y * x),
+    priority: 6,
+    assoc: Assoc::Left,
+    doc: Doc::new(Arithmetic, "Multiplication of values side by side"),
+};
+pub const DIV: Function = Function::binary(
+    "/",
+    6,
+    Doc::new(Arithmetic, "Division, exact for integers and rationals")
+        .args(&["dividend", "divisor"])
+        .domain("divisor not 0, NaN otherwise")
+        .examples(&[("7/2", "7/2"), ("1/0", "NaN")]),
+    |x, y| y / x,
+);
 // https://en.wikipedia.org/wiki/Modulo#Variants_of_the_definition
 // Truncated
-pub const REM: Function = Function::binary("%", 6, |x, y| y % x);
-pub const POW: Function = Function::binary("^", 7, |x, y| y.pow(x));
+pub const REM: Function = Function::binary(
+    "%",
+    6,
+    Doc::new(
+        Arithmetic,
+        "Remainder of truncated division, with the sign of the dividend",
+    )
+    .args(&["dividend", "divisor"])
+    .examples(&[("7%3", "1"), ("-7%3", "-1")]),
+    |x, y| y % x,
+);
+pub const POW: Function = Function::right(
+    "^",
+    7,
+    Doc::new(Arithmetic, "Exponentiation, grouped from the right")
+        .args(&["base", "exponent"])
+        .examples(&[("2^10", "1024"), ("(1/2)^2", "1/4"), ("2^3^2", "512")]),
+    |x, y| y.pow(x),
+);
+pub const NOT: Function = Function::unary(
+    "~",
+    6,
+    Doc::new(Bitwise, "Flips every bit, in two's complement")
+        .args(&["x"])
+        .domain("integers, NaN otherwise")
+        .examples(&[("~5", "-6"), ("~0xff & 0xfff", "3840")]),
+    |x| !x,
+);
+pub const AND: Function = Function::binary(
+    "&",
+    2,
+    Doc::new(Bitwise, "Bitwise and")
+        .args(&["x", "y"])
+        .domain("integers, NaN otherwise")
+        .examples(&[("6 & 3", "2"), ("0xabcd & 0xff", "205")]),
+    |x, y| y & x,
+);
+pub const OR: Function = Function::binary(
+    "|",
+    1,
+    Doc::new(Bitwise, "Bitwise or")
+        .args(&["x", "y"])
+        .domain("integers, NaN otherwise")
+        .examples(&[("6 | 3", "7"), ("1<<4 | 1", "17")]),
+    |x, y| y | x,
+);
+pub const XOR: Function = Function::binary(
+    "xor",
+    1,
+    Doc::new(Bitwise, "Bitwise exclusive or")
+        .args(&["x", "y"])
+        .domain("integers, NaN otherwise")
+        .examples(&[("6 xor 3", "5"), ("xor(5, 5)", "0")]),
+    |x, y| y ^ x,
+);
+pub const SHL: Function = Function::binary(
+    "<<",
+    3,
+    Doc::new(Bitwise, "Shifts the bits of x left, multiplying by 2^n")
+        .args(&["x", "n"])
+        .domain("integer x, and n >= 0, NaN otherwise")
+        .examples(&[("1 << 10", "1024"), ("1 << 2+1", "8")]),
+    |x, y| y << x,
+);
+pub const SHR: Function = Function::binary(
+    ">>",
+    3,
+    Doc::new(
+        Bitwise,
+        "Shifts the bits of x right, dividing by 2^n and rounding down",
+    )
+    .args(&["x", "n"])
+    .domain("integer x, and n >= 0, NaN otherwise")
+    .examples(&[("1024 >> 3", "128"), ("-7 >> 1", "-4")]),
+    |x, y| y >> x,
+);
+pub const POPCOUNT: Function = Function::unary(
+    "popcount",
+    4,
+    Doc::new(Bitwise, "The number of 1 bits")
+        .args(&["x"])
+        .domain("integers >= 0, NaN otherwise; use :bits for negative words")
+        .examples(&[("popcount 255", "8"), ("popcount 0b1011", "3")]),
+    |x| x.popcount(),
+);
+pub const IN: Function = Function::binary(
+    "in",
+    0,
+    Doc::new(Other, "Converts x to a unit, e.g. a duration to weeks")
+        .args(&["x", "unit"])
+        .examples(&[("90 days in weeks", "90/7"), ("3h 30min in h", "7/2")]),
+    |x, y| y / x,
+);
+pub const COUNT: Function = Function::stat(
+    "count",
+    Doc::new(Statistics, "The number of values")
+        .args(&["xs"])
+        .examples(&[("count{1, 2, 3}", "3"), ("count([])", "0")]),
+    stats::count,
+);
+pub const SUM: Function = Function::stat(
+    "sum",
+    Doc::new(
+        Statistics,
+        "The sum of every value, or sum(term, k, a, b) of term for k from a to b, which can be inf",
+    )
+    .args(&["xs"])
+    .examples(&[
+        ("sum{1, 2, 3}", "6"),
+        ("sum(1/2, 1/3)", "5/6"),
+        ("sum(k^2, k, 1, 100)", "338350"),
+    ]),
+    stats::sum,
+);
+pub const MEAN: Function = Function::stat(
+    "mean",
+    Doc::new(Statistics, "The arithmetic mean")
+        .args(&["xs"])
+        .domain("at least one value, NaN otherwise")
+        .examples(&[("mean{1, 2, 4}", "7/3"), ("mean{0.5, 1.5}", "1")]),
+    stats::mean,
+);
+pub const MEDIAN: Function = Function::stat(
+    "median",
+    Doc::new(
+        Statistics,
+        "The middle value, or the mean of the two middle ones",
+    )
+    .args(&["xs"])
+    .domain("at least one value, NaN otherwise")
+    .examples(&[("median{3, 1, 2}", "2"), ("median{1, 2, 3, 10}", "5/2")]),
+    stats::median,
+);
+pub const MODE: Function = Function::stat(
+    "mode",
+    Doc::new(
+        Statistics,
+        "The most frequent value, the smallest one on ties",
+    )
+    .args(&["xs"])
+    .domain("at least one value, NaN otherwise")
+    .examples(&[("mode{1, 2, 2, 3}", "2"), ("mode{3, 1}", "1")]),
+    stats::mode,
+);
+pub const VAR: Function = Function::stat(
+    "var",
+    Doc::new(Statistics, "The sample variance, dividing by n - 1")
+        .args(&["xs"])
+        .domain("at least two values, NaN otherwise")
+        .examples(&[("var{1, 2, 3, 4}", "5/3")]),
+    stats::var,
+);
+pub const STDEV: Function = Function::stat(
+    "stdev",
+    Doc::new(
+        Statistics,
+        "The sample standard deviation, the square root of var",
+    )
+    .args(&["xs"])
+    .domain("at least two values, NaN otherwise")
+    .examples(&[("stdev{1, 3}", "1.4142135623730951")]),
+    stats::stdev,
+);
+pub const MIN: Function = Function::stat(
+    "min",
+    Doc::new(
+        Statistics,
+        "The smallest value; min without parentheses is a minute",
+    )
+    .args(&["xs"])
+    .domain("at least one value, NaN otherwise")
+    .examples(&[("min(3, 1/2, 2)", "1/2"), ("min({-1, 1})", "-1")]),
+    stats::min,
+);
+pub const MAX: Function = Function::stat(
+    "max",
+    Doc::new(Statistics, "The largest value")
+        .args(&["xs"])
+        .domain("at least one value, NaN otherwise")
+        .examples(&[("max(3, 1/2, 2)", "3"), ("max{-1, 1}", "1")]),
+    stats::max,
+);
+pub const QUANTILE: Function = Function {
+    name: Cow::Borrowed("quantile"),
+    arity: 2,
+    f: F::StatWith(stats::quantile),
+    priority: 4,
+    assoc: Assoc::Left,
+    doc: Doc::new(
+        Statistics,
+        "The value below which a fraction q of the values are, interpolated",
+    )
+    .args(&["xs", "q"])
+    .domain("at least one value and 0 <= q <= 1, NaN otherwise")
+    .examples(&[
+        ("quantile({1, 2, 3, 4}, 1/4)", "7/4"),
+        ("quantile({5, 1}, 1)", "5"),
+    ]),
+};
+pub const POLY: Function = Function::polynomial(
+    "poly",
+    1,
+    Doc::new(
+        Polynomials,
+        "The polynomial of the unknowns in x, e.g. poly(x^3 - 1), which can be divided with / and % or evaluated as p(2)",
+    )
+    .args(&["x"])
+    .examples(&[("poly(x + 1)^2 - x^2 - 2x", "1"), ("poly 5", "5")]),
+    value::poly,
+);
+pub const DERIV: Function = Function::polynomial(
+    "deriv",
+    1,
+    Doc::new(
+        Polynomials,
+        "The derivative of a polynomial of one variable",
+    )
+    .args(&["p"])
+    .examples(&[("deriv(x^3) - 3x^2", "0"), ("degree deriv(x^2 + x)", "1")]),
+    value::deriv,
+);
+pub const DERIV_BY: Function = Function::polynomial(
+    "deriv",
+    2,
+    Doc::new(
+        Polynomials,
+        "The derivative of a polynomial with respect to x",
+    )
+    .args(&["p", "x"])
+    .examples(&[("deriv(x^2 y, y) - x^2", "0"), ("deriv(5, x)", "0")]),
+    value::deriv_by,
+);
+pub const DEGREE: Function = Function::polynomial(
+    "degree",
+    1,
+    Doc::new(
+        Polynomials,
+        "The highest sum of powers in a term of a polynomial, 0 for numbers",
+    )
+    .args(&["p"])
+    .examples(&[
+        ("degree(x^3 - 1)", "3"),
+        ("degree(x^2 y + y)", "3"),
+        ("degree 5", "0"),
+    ]),
+    value::degree,
+);
+pub const COEFFS: Function = Function::polynomial(
+    "coeffs",
+    1,
+    Doc::new(
+        Polynomials,
+        "The coefficients of a polynomial of one variable, from the highest power down",
+    )
+    .args(&["p"])
+    .examples(&[("sum coeffs((x + 1)^3)", "8"), ("coeffs 5", "{5}")]),
+    value::coeffs,
+);
+pub const GCD: Function = Function::polynomial(
+    "gcd",
+    2,
+    Doc::new(
+        Polynomials,
+        "The greatest common divisor of integers, or of polynomials of one variable",
+    )
+    .args(&["a", "b"])
+    .domain("integers and polynomials, NaN otherwise")
+    .examples(&[
+        ("gcd(12, 18)", "6"),
+        ("degree gcd(x^2 - 1, x^2 + 2x + 1)", "1"),
+    ]),
+    value::gcd,
+);
+
+#[cfg(test)]
+mod test {
+    use crate::env::Env;
+    use crate::interpreter::run_value;
+
+    #[test]
+    fn _examples() {
+        for (name, fs) in Env::prelude().functions() {
+            for f in [&fs.unary, &fs.binary].into_iter().flatten() {
+                assert!(!f.doc.examples.is_empty(), "{} has no examples", name);
+                for (input, expected) in f.doc.examples {
+                    let result = run_value(input, &mut Env::prelude()).map(|it| it.to_string());
+                    assert_eq!(result.as_deref(), Ok(*expected), "{}", input);
+                }
+            }
+        }
+    }
+}
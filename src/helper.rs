@@ -0,0 +1,298 @@
+use std::borrow::Cow;
+use std::cell::Cell;
+
+use rustyline::completion::Completer;
+use rustyline::highlight::{CmdKind, Highlighter};
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Helper};
+
+use crate::command::command_line;
+use crate::env::{Env, Variable};
+use crate::parser::{self, Stmt};
+use crate::pratt;
+use crate::s::{self, Infix};
+use crate::token::TokenType;
+
+const NUMBER: &str = "\x1b[33m";
+const FUNCTION: &str = "\x1b[36m";
+const VARIABLE: &str = "\x1b[32m";
+const OPERATOR: &str = "\x1b[35m";
+const TEXT: &str = "\x1b[31m";
+const COMMENT: &str = "\x1b[90m";
+const BRACKET: &str = "\x1b[1;34m";
+const HINT: &str = "\x1b[90m";
+const RESET: &str = "\x1b[0m";
+
+// The most work the preview does on every key press, see s::cost
+const PREVIEW_COST: usize = 10_000;
+
+// Completes, highlights and previews the value of what's being typed in the REPL,
+// using the variables and functions in `env`.
+pub struct CwimHelper<'f> {
+    pub env: Env<'f>,
+    // The position of the bracket under the cursor, if any
+    bracket: Cell<Option<usize>>,
+}
+
+impl<'f> CwimHelper<'f> {
+    pub fn new(env: Env<'f>) -> Self {
+        Self {
+            env,
+            bracket: Cell::new(None),
+        }
+    }
+
+    fn candidates(&self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let start = line[..pos]
+            .char_indices()
+            .rfind(|(_, c)| !(c.is_alphabetic() || *c == '_'))
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        let prefix = &line[start..pos];
+        if prefix.is_empty() {
+            return (pos, vec![]);
+        }
+        let mut candidates: Vec<_> = self
+            .env
+            .names()
+            .filter(|it| it.starts_with(prefix))
+            .map(|it| it.to_owned())
+            .collect();
+        candidates.sort();
+        (start, candidates)
+    }
+
+    // How the line will be read, e.g. ` = 2^(x+y)` for `2^ x+y`, and its value
+    // when it's an expression that's cheap enough. Assignments are only parsed,
+    // never run.
+    fn preview(&self, line: &str) -> Option<String> {
+        if command_line(line, &self.env).is_some() || parser::statements(line).len() != 1 {
+            return None;
+        }
+        let code = parser::strip_comment(line).trim();
+        let compact: String = code.split_whitespace().collect();
+        let mut hint = String::new();
+        match parser::stmt(line, &self.env).ok()? {
+            Stmt::Expr(mut tokens) => {
+                let s = pratt::expr(&mut tokens, &self.env).ok()?;
+                let infix = Infix(&s).to_string();
+                if infix != compact {
+                    hint += &format!(" = {}", infix);
+                }
+                if s::cost(&s) > PREVIEW_COST {
+                    return Some(hint).filter(|it| !it.is_empty());
+                }
+                let value = s::eval_env(&s, &self.env);
+                if let Ok(value) = value.map(|(it, _)| self.env.settings.show_value(&it)) {
+                    if value != infix && value != code {
+                        hint += &format!(" = {}", value);
+                    }
+                }
+            }
+            Stmt::Assignment(mut lhs, mut rhs) => {
+                let lhs = Infix(&pratt::expr(&mut lhs, &self.env).ok()?).to_string();
+                let rhs = Infix(&pratt::expr(&mut rhs, &self.env).ok()?).to_string();
+                if format!("{}={}", lhs, rhs) != compact {
+                    hint += &format!(" ⇒ {} = {}", lhs, rhs);
+                }
+            }
+            Stmt::Definition(name, _, mut tokens) => {
+                let body = Infix(&pratt::expr(&mut tokens, &self.env).ok()?).to_string();
+                if format!("{}:={}", name, body) != compact {
+                    hint += &format!(" ⇒ {} := {}", name, body);
+                }
+            }
+        }
+        Some(hint).filter(|it| !it.is_empty())
+    }
+
+    fn color(&self, ttype: &TokenType, lexeme: &str) -> Option<&'static str> {
+        match ttype {
+            TokenType::Literal(_) | TokenType::Superscript(_) | TokenType::Value(_) => Some(NUMBER),
+            TokenType::Symbol => Some(OPERATOR),
+            TokenType::Comment => Some(COMMENT),
+            TokenType::Text => Some(TEXT),
+            TokenType::Identifier => match self.env.get(lexeme) {
+                Some(Variable::Function(_)) => Some(FUNCTION),
+                Some(_) => Some(VARIABLE),
+                None => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+// The position of the bracket matching the one at `pos`
+fn matching_bracket(line: &str, pos: usize) -> Option<usize> {
+    let bytes = line.as_bytes();
+    let (step, open, close): (isize, u8, u8) = match bytes.get(pos)? {
+        b'(' => (1, b'(', b')'),
+        b')' => (-1, b')', b'('),
+        b'[' => (1, b'[', b']'),
+        b']' => (-1, b']', b'['),
+        b'{' => (1, b'{', b'}'),
+        b'}' => (-1, b'}', b'{'),
+        _ => return None,
+    };
+    let mut depth = 0;
+    let mut i = pos as isize;
+    while let Some(c) = usize::try_from(i).ok().and_then(|it| bytes.get(it)) {
+        if *c == open {
+            depth += 1;
+        } else if *c == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i as usize);
+            }
+        }
+        i += step;
+    }
+    None
+}
+
+impl<'f> Completer for CwimHelper<'f> {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self.candidates(line, pos))
+    }
+}
+
+impl<'f> Hinter for CwimHelper<'f> {
+    type Hint = String;
+
+    fn hint(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> Option<String> {
+        if pos < line.len() {
+            return None;
+        }
+        self.preview(line)
+    }
+}
+
+impl<'f> Highlighter for CwimHelper<'f> {
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
+        if line.trim_start().starts_with(':') {
+            return Cow::Borrowed(line);
+        }
+        let brackets = self
+            .bracket
+            .get()
+            .and_then(|pos| Some((pos, matching_bracket(line, pos)?)));
+        let (tokens, rest) = parser::lex_prefix(line, &self.env);
+        let mut result = String::new();
+        let mut offset = 0;
+        for token in tokens {
+            let is_bracket = brackets.is_some_and(|(a, b)| offset == a || offset == b);
+            let color = if is_bracket {
+                Some(BRACKET)
+            } else {
+                self.color(&token.ttype, token.lexeme)
+            };
+            match color {
+                Some(color) => result += &format!("{}{}{}", color, token.lexeme, RESET),
+                None => result += token.lexeme,
+            }
+            offset += token.lexeme.len();
+        }
+        Cow::Owned(result + rest)
+    }
+
+    fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+        Cow::Owned(format!("{}{}{}", HINT, hint, RESET))
+    }
+
+    fn highlight_char(&self, line: &str, pos: usize, kind: CmdKind) -> bool {
+        if kind == CmdKind::ForcedRefresh {
+            self.bracket.set(None);
+            return false;
+        }
+        // The bracket under the cursor, or the one just before it
+        let bracket = [Some(pos), pos.checked_sub(1)]
+            .into_iter()
+            .flatten()
+            .find(|it| matching_bracket(line, *it).is_some());
+        let changed = self.bracket.get() != bracket;
+        self.bracket.set(bracket);
+        // Typing can change the colors of the whole line
+        changed || kind == CmdKind::Other
+    }
+}
+
+impl<'f> Validator for CwimHelper<'f> {}
+
+impl<'f> Helper for CwimHelper<'f> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::number::Number;
+
+    #[test]
+    fn _complete() {
+        let mut env = Env::prelude();
+        env.assign("cost".to_owned(), Number::from(3));
+        let helper = CwimHelper::new(env);
+        assert_eq!(
+            helper.candidates("2 cos", 5),
+            (
+                2,
+                vec!["cos".to_owned(), "cosh".to_owned(), "cost".to_owned()]
+            )
+        );
+        assert_eq!(helper.candidates("2 +", 3), (3, vec![]));
+    }
+
+    #[test]
+    fn _preview() {
+        let mut env = Env::prelude();
+        env.assign("x".to_owned(), Number::from(3));
+        let helper = CwimHelper::new(env);
+        assert_eq!(helper.preview("2^10 # bytes"), Some(" = 1024".to_owned()));
+        assert_eq!(helper.preview("2^ x+1"), Some(" = 2^(x+1) = 16".to_owned()));
+        assert_eq!(helper.preview("2^ y+1"), Some(" = 2^(y+1)".to_owned()));
+        assert_eq!(helper.preview("42"), None);
+        assert_eq!(helper.preview("x = 2"), None);
+        assert_eq!(
+            helper.preview("y = 2^ x+1"),
+            Some(" ⇒ y = 2^(x+1)".to_owned())
+        );
+        assert_eq!(helper.preview("a := 2 x"), Some(" ⇒ a := 2*x".to_owned()));
+        assert_eq!(helper.preview("2 +"), None);
+        let long = format!("sum{{{}}}", vec!["1"; 20_000].join(", "));
+        assert!(!helper.preview(&long).unwrap().ends_with(" = 20000"));
+        assert_eq!(
+            helper.preview("sum(1/k, k, 1, 20000)"),
+            Some(" = sum(1/k, k, 1, 20000)".to_owned())
+        );
+        // Files are only read when the line is run
+        assert_eq!(
+            helper.preview("count load(\"/dev/stdin\")"),
+            Some(" = count(load(\"/dev/stdin\"))".to_owned())
+        );
+        assert_eq!(
+            helper.preview("fit({0, 1, 2}, {-1, 0, 5}, 2)"),
+            Some(" = fit({0, 1, 2}, {-1, 0, 5}, 2) = 2x² - x - 1".to_owned())
+        );
+    }
+
+    #[test]
+    fn _highlight() {
+        let helper = CwimHelper::new(Env::prelude());
+        assert_eq!(
+            helper.highlight("2 cos(x) ?", 0),
+            "\x1b[33m2\x1b[0m \x1b[36mcos\x1b[0m(x) ?"
+        );
+        helper.bracket.set(Some(5));
+        assert_eq!(
+            helper.highlight("cos(x)", 0),
+            "\x1b[36mcos\x1b[0m\x1b[1;34m(\x1b[0mx\x1b[1;34m)\x1b[0m"
+        );
+        assert_eq!(matching_bracket("((1)", 0), None);
+        assert_eq!(matching_bracket("((1)", 3), Some(1));
+    }
+}
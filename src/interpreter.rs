@@ -1,10 +1,13 @@
-use crate::env::Env;
+use crate::env::{Env, Variable};
 use crate::function::*;
 use crate::number::Number;
 use crate::parser::*;
 use crate::polynomial::polynomial;
 use crate::pratt;
 use crate::s;
+use crate::time;
+use crate::token::{Token, TokenType};
+use crate::value::Value;
 use std::fmt::Debug;
 use std::fmt::Formatter;
 
@@ -25,30 +28,100 @@ impl<'f> Debug for Expr<'f> {
     }
 }
 
+// The variable on the lhs of `x = ...`, which is assigned rather than solved for
+// so that names which already hold a value can be changed. Functions which
+// are also units, like min, read as values and can be assigned too.
+fn assigns_name<'a>(lhs: &[Token<'a>], env: &Env) -> Option<&'a str> {
+    match lhs
+        .iter()
+        .filter(|it| it.ttype != TokenType::Space)
+        .collect::<Vec<_>>()[..]
+    {
+        [Token {
+            ttype: TokenType::Identifier,
+            lexeme,
+            ..
+        }] if !matches!(env.get(lexeme), Some(Variable::Function(_)))
+            || time::unit(lexeme).is_some() =>
+        {
+            Some(lexeme)
+        }
+        _ => None,
+    }
+}
+
 pub fn run(text: &str, env: &mut Env) -> Parsed<Number> {
-    let tks = stmt(text, env)?;
-    match tks {
-        Stmt::Expr(mut tks) => Ok(s::eval(&pratt::expr(&mut tks, env)?)?),
+    run_value(text, env)?.into_number()
+}
+
+// Like `run`, for statements whose value can also be a date or a duration
+pub fn run_value(text: &str, env: &mut Env) -> Parsed<Value> {
+    match run_stmt(text, env)? {
+        Outcome::Value(value) => Ok(value),
+        Outcome::Pending(note) => Err(note),
+    }
+}
+
+// What running a statement gives
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Value(Value),
+    // A definition stored without a value, and why, e.g. as its names aren't bound yet
+    Pending(String),
+}
+
+// Like `run_value`, for definitions that are stored before they can be evaluated
+pub fn run_stmt(text: &str, env: &mut Env) -> Parsed<Outcome> {
+    match stmt(text, env)? {
+        Stmt::Expr(mut tks) => {
+            let (result, overflow) = s::eval_env(&pratt::expr(&mut tks, env)?, env)?;
+            env.overflow = overflow;
+            Ok(Outcome::Value(result))
+        }
         Stmt::Assignment(mut lhs, mut rhs) => {
+            if let Some(name) = assigns_name(&lhs, env) {
+                let (result, overflow) = s::eval_env(&pratt::expr(&mut rhs, env)?, env)?;
+                env.overflow = overflow;
+                env.assign(name.to_owned(), result.clone());
+                return Ok(Outcome::Value(result));
+            }
             let expr = pratt::expr(&mut lhs, env)?;
             let mut p = polynomial(&expr)?;
             // example: in x^2 + 2x = 6+5, result = 11
             let result = s::eval(&pratt::expr(&mut rhs, env)?)?;
             p -= result;
+            let unknown = match p.variables()[..] {
+                [] => String::new(),
+                [x] => x.to_owned(),
+                ref several => {
+                    return Err(format!("Can't solve for {} at once", several.join(" and ")))
+                }
+            };
             // TODO: Allow multiple solutions to be assigned.
             let roots = p.roots();
             match &roots[..] {
                 [root] => {
-                    env.assign(p.unknown.to_owned(), &root);
-                    Ok(root.clone())
+                    env.assign(unknown, root);
+                    Ok(Outcome::Value(root.into()))
                 }
                 [root1, root2] => {
                     println!("{}, {}", root1, root2);
-                    env.assign(p.unknown.to_owned(), &root1);
-                    Ok(root1.clone())
+                    env.assign(unknown, root1);
+                    Ok(Outcome::Value(root1.into()))
                 }
                 _ => Err("no solution found".to_owned()),
             }
         }
+        Stmt::Definition(name, body, _) => {
+            env.define(name, body)?;
+            match env.value(name) {
+                Ok(value) => Ok(Outcome::Value(value)),
+                // Stored all the same, to be evaluated once its names are bound
+                Err(msg) => Ok(Outcome::Pending(format!(
+                    "{} is defined, but can't be evaluated yet: {}",
+                    name, msg
+                ))),
+            }
+        }
     }
 }
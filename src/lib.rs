@@ -1,5 +1,12 @@
+pub mod calculator;
+pub mod calculus;
+pub mod command;
+pub mod currency;
+pub mod data;
 pub mod env;
+pub mod error;
 pub mod function;
+pub mod helper;
 pub mod interpreter;
 pub mod number;
 pub mod parser;
@@ -7,4 +14,12 @@ pub mod polynomial;
 pub mod pratt;
 pub mod prioritize;
 pub mod s;
+pub mod script;
+pub mod series;
+pub mod session;
+pub mod settings;
+pub mod stats;
+pub mod time;
 pub mod token;
+pub mod value;
+pub mod word;
@@ -1,34 +1,231 @@
 use std::io;
-use std::io::IsTerminal;
+use std::io::{IsTerminal, Read};
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::process::ExitCode;
 
+use cwim::command::{run_command, Commands};
+use cwim::currency::{self, Rates};
 use cwim::env::*;
+use cwim::helper::CwimHelper;
 use cwim::interpreter::*;
+use cwim::parser::{statements, Parsed};
+use cwim::script::{self, Line};
+use cwim::session;
 use rustyline::error::ReadlineError;
-use rustyline::{DefaultEditor, Result};
+use rustyline::history::FileHistory;
+use rustyline::{Editor, Result};
 
-fn run_line(line: &str, env: &mut Env) {
-    match run(line, env) {
-        Ok(result) => {
-            println!("{}", result);
-            env.assign("ans".to_owned(), &result);
+const USAGE: &str = "\
+Usage: cwim [OPTIONS] [FILE]
+
+Runs the statements in FILE (- for stdin) or given with -e.
+Without either, starts a REPL, or reads statements from stdin if it is not a terminal.
+
+Options:
+  -e, --eval EXPR      Evaluate EXPR, can be repeated
+  -q, --quiet          Only print the last value
+      --format FORMAT  Print values as text (default) or json
+      --rates FILE     Read exchange rates from a .csv or .toml file, instead of
+                       .cwim_rates.toml or .cwim_rates.csv if there is one
+  -h, --help           Print this help";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Text,
+    Json,
+}
+
+struct Options {
+    help: bool,
+    quiet: bool,
+    format: Format,
+    exprs: Vec<String>,
+    file: Option<String>,
+    rates: Option<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Parsed<Options> {
+    let mut options = Options {
+        help: false,
+        quiet: false,
+        format: Format::Text,
+        exprs: vec![],
+        file: None,
+        rates: None,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => options.help = true,
+            "-q" | "--quiet" => options.quiet = true,
+            "-e" | "--eval" => match args.next() {
+                Some(expr) => options.exprs.push(expr),
+                None => return Err(format!("{} expects an expression", arg)),
+            },
+            "--format" => match args.next().as_deref() {
+                Some("text") => options.format = Format::Text,
+                Some("json") => options.format = Format::Json,
+                Some(other) => return Err(format!("Unknown format '{}'", other)),
+                None => return Err("--format expects text or json".to_owned()),
+            },
+            "--rates" => match args.next() {
+                Some(file) => options.rates = Some(file),
+                None => return Err("--rates expects a file".to_owned()),
+            },
+            flag if flag.starts_with('-') && flag != "-" => {
+                return Err(format!("Unknown option '{}'", flag))
+            }
+            _ if options.file.is_some() => return Err("Expected a single file".to_owned()),
+            _ => options.file = Some(arg),
+        }
+    }
+    if options.file.is_some() && !options.exprs.is_empty() {
+        return Err("Expected either a file or -e, not both".to_owned());
+    }
+    Ok(options)
+}
+
+// Runs a command, or the statements on a line one after the other, passing
+// the value of every statement that should be printed to `show` with its
+// number in the history, written in the base from the settings. Breaks after
+// `:quit`.
+fn run_line(
+    line: &str,
+    commands: &Commands,
+    env: &mut Env,
+    show: &mut impl FnMut(usize, String),
+) -> Parsed<ControlFlow<()>> {
+    if let Some(result) = run_command(commands, line, env) {
+        return result;
+    }
+    for (statement, semicolon) in statements(line) {
+        let result = match run_stmt(statement, env)? {
+            Outcome::Value(value) => value,
+            Outcome::Pending(note) => {
+                eprintln!("{}", note);
+                continue;
+            }
+        };
+        let index = env.record(statement.trim(), result.clone());
+        if !(semicolon && env.settings.suppress) {
+            let mut text = env.settings.show_value(&result);
+            if env.overflow {
+                text += " (overflow)";
+            }
+            show(index, text);
+        }
+    }
+    Ok(ControlFlow::Continue(()))
+}
+
+fn json_string(text: &str) -> String {
+    let mut result = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => result += "\\\"",
+            '\\' => result += "\\\\",
+            '\n' => result += "\\n",
+            c if c.is_control() => result += &format!("\\u{:04x}", c as u32),
+            c => result.push(c),
+        }
+    }
+    result + "\""
+}
+
+fn print_value(line: &Line, value: &str, format: Format) {
+    match format {
+        Format::Text => println!("{}", value),
+        Format::Json => println!(
+            "{{\"input\":{},\"value\":{}}}",
+            json_string(line.text.trim()),
+            json_string(value)
+        ),
+    }
+}
+
+fn print_error(line: &Line, msg: &str, format: Format) {
+    match format {
+        Format::Text => eprintln!("{}: {}", line, msg),
+        Format::Json => eprintln!(
+            "{{\"input\":{},\"error\":{},\"origin\":{},\"line\":{}}}",
+            json_string(line.text.trim()),
+            json_string(msg),
+            json_string(&line.origin),
+            line.number
+        ),
+    }
+}
+
+// Runs every line, even after errors, and fails if any of them did.
+fn run_script(lines: &[Line], env: &mut Env, options: &Options) -> ExitCode {
+    let mut status = ExitCode::SUCCESS;
+    let mut last = None;
+    let commands = Commands::builtin();
+    for line in lines {
+        let result = run_line(&line.text, &commands, env, &mut |_, value| {
+            if options.quiet {
+                last = Some((line, value));
+            } else {
+                print_value(line, &value, options.format);
+            }
+        });
+        match result {
+            Ok(ControlFlow::Continue(())) => {}
+            Ok(ControlFlow::Break(())) => break,
+            Err(msg) => {
+                print_error(line, &msg, options.format);
+                status = ExitCode::FAILURE;
+            }
         }
-        Err(msg) => eprintln!("{}", msg),
     }
+    if let Some((line, value)) = last {
+        print_value(line, &value, options.format);
+    }
+    status
 }
 
-fn repl() -> Result<()> {
+// A fresh environment with the exchange rates of the options
+fn prelude(options: &Options) -> Parsed<Env<'static>> {
     let mut env = Env::prelude();
-    let mut rl = DefaultEditor::new()?;
+    let file = match &options.rates {
+        Some(file) => Some(Path::new(file)),
+        None => currency::DEFAULT_FILES
+            .iter()
+            .map(Path::new)
+            .find(|it| it.exists()),
+    };
+    if let Some(file) = file {
+        env.rates = Rates::load(file)?;
+    }
+    Ok(env)
+}
+
+fn repl(mut env: Env<'static>) -> Result<()> {
+    let autosave = Path::new(session::AUTOSAVE);
+    if autosave.exists() {
+        if let Err(msg) = session::load(&mut env, autosave) {
+            eprintln!("{}", msg);
+        }
+    }
+    let mut rl: Editor<CwimHelper, FileHistory> = Editor::new()?;
+    rl.set_helper(Some(CwimHelper::new(env)));
     let history = ".cwim_history";
     if rl.load_history(history).is_err() {
         println!("No previous history.");
     }
+    let commands = Commands::builtin();
     loop {
         let readline = rl.readline("cwim> ");
         match readline {
             Ok(line) => {
                 let _ = rl.add_history_entry(line.as_str());
-                run_line(&line, &mut env);
+                let env = &mut rl.helper_mut().expect("helper was set").env;
+                let mut show = |index, result| println!("[{}] = {}", index, result);
+                match run_line(&line, &commands, env, &mut show) {
+                    Ok(ControlFlow::Continue(())) => {}
+                    Ok(ControlFlow::Break(())) => break,
+                    Err(msg) => eprintln!("{}", msg),
+                }
             }
             Err(ReadlineError::Interrupted) => {
                 eprintln!("Interrupted");
@@ -43,17 +240,73 @@ fn repl() -> Result<()> {
             }
         }
     }
+    let env = &rl.helper().expect("helper was set").env;
+    let saved = if env.settings.autorestore {
+        session::save(env, autosave)
+    } else if autosave.exists() {
+        // Don't restore a stale session next time
+        std::fs::remove_file(autosave).map_err(|e| e.to_string())
+    } else {
+        Ok(())
+    };
+    if let Err(msg) = saved {
+        eprintln!("{}", msg);
+    }
     rl.save_history(history)
 }
 
-fn main() {
-    let stdin = io::stdin();
-    if stdin.is_terminal() {
-        let _ = repl();
-    } else {
-        let mut env = Env::prelude();
-        for line in stdin.lines() {
-            run_line(&line.expect("no line found"), &mut env);
+fn read_stdin() -> Parsed<Vec<Line>> {
+    let mut text = String::new();
+    io::stdin()
+        .read_to_string(&mut text)
+        .map_err(|e| format!("Can't read stdin: {}", e))?;
+    script::lines(&text, "<stdin>", Path::new("."))
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(msg) => {
+            eprintln!("{}\n\n{}", msg, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    if options.help {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    let mut env = match prelude(&options) {
+        Ok(env) => env,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            return ExitCode::from(2);
+        }
+    };
+    let lines = match options.file.as_deref() {
+        Some("-") => read_stdin(),
+        Some(file) => script::load(Path::new(file)),
+        None if !options.exprs.is_empty() => options
+            .exprs
+            .iter()
+            .map(|expr| script::lines(expr, "-e", Path::new(".")))
+            .collect::<Parsed<Vec<_>>>()
+            .map(|it| it.concat()),
+        None if io::stdin().is_terminal() => {
+            return match repl(env) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(err) => {
+                    eprintln!("Error: {:?}", err);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        None => read_stdin(),
+    };
+    match lines {
+        Ok(lines) => run_script(&lines, &mut env, &options),
+        Err(msg) => {
+            eprintln!("{}", msg);
+            ExitCode::from(2)
         }
     }
 }
@@ -4,7 +4,7 @@ use num::{
     pow::Pow,
     rational::Ratio,
     traits::{ParseFloatError, ToPrimitive},
-    BigInt, Num, One, Signed, Zero,
+    BigInt, Integer, Num, One, Signed, Zero,
 };
 
 #[derive(Debug, Clone, PartialEq)]
@@ -60,7 +60,15 @@ impl Num for Number {
             return Ok(Number::Rat(n));
         }
         let parts: Vec<_> = str.split('.').collect();
+        // radix^digits, as a power of 16 quickly overflows a u32
+        let scale = |digits: &str| BigInt::from(radix).pow(digits.len() as u32);
         match parts[..] {
+            // A trailing dot asks for a float, except in other bases, e.g. 0x1. is 1
+            [before, ""] if radix != 10 => {
+                if let Ok(whole) = <BigInt as Num>::from_str_radix(before, radix) {
+                    return Ok(Number::Int(whole));
+                }
+            }
             [before, ""] => {
                 if let Ok(whole) = <f64 as Num>::from_str_radix(before, radix) {
                     return Ok(Number::Flt(whole));
@@ -68,10 +76,7 @@ impl Num for Number {
             }
             ["", after] => {
                 if let Ok(decimal) = <BigInt as Num>::from_str_radix(after, radix) {
-                    return Ok(Number::Rat(Ratio::new(
-                        decimal,
-                        BigInt::from(radix.pow(after.len().try_into().unwrap())),
-                    )));
+                    return Ok(Number::Rat(Ratio::new(decimal, scale(after))));
                 }
             }
             [before, after] => {
@@ -79,12 +84,7 @@ impl Num for Number {
                     <BigInt as Num>::from_str_radix(before, radix),
                     <BigInt as Num>::from_str_radix(after, radix),
                 ) {
-                    return Ok(Number::Rat(
-                        Ratio::new(
-                            decimal,
-                            BigInt::from(radix.pow(after.len().try_into().unwrap())),
-                        ) + whole,
-                    ));
+                    return Ok(Number::Rat(Ratio::new(decimal, scale(after)) + whole));
                 }
             }
             _ => {}
@@ -96,7 +96,76 @@ impl Num for Number {
     }
 }
 
+// Whether every prime factor of n divides radix, so that 1/n ends in that base
+fn is_power_of(n: &BigInt, radix: u32) -> bool {
+    let radix = BigInt::from(radix);
+    let mut n = n.clone();
+    loop {
+        let g = n.gcd(&radix);
+        if g.is_one() {
+            return n.is_one();
+        }
+        while (&n % &g).is_zero() {
+            n /= &g;
+        }
+    }
+}
+
+// A float rounded to about as many digits in `radix` as it holds
+fn round_radix(x: Ratio<BigInt>, radix: u32) -> Ratio<BigInt> {
+    let bits = f64::from(radix).log2();
+    let whole = x.abs().to_integer().bits() as f64 / bits;
+    let places = (50. / bits - whole).floor().max(0.) as u32;
+    let scale = Ratio::from_integer(BigInt::from(radix).pow(places));
+    (x * &scale).round() / scale
+}
+
 impl Number {
+    // The number written in `radix`, in a way cwim reads back, e.g. 0x1.8,
+    // 3#12 or 12:30:15 for base 60. Fractions that don't end in that base are
+    // written as a ratio, e.g. 0x1/0x3.
+    pub fn to_string_radix(&self, radix: u32) -> String {
+        let exact = match self {
+            Number::Flt(x) => match Ratio::from_float(*x) {
+                Some(r) => round_radix(r, radix),
+                None => return self.to_string(),
+            },
+            Number::Int(n) => Ratio::from_integer(n.clone()),
+            Number::Rat(r) => r.clone(),
+        };
+        let sign = if exact.is_negative() { "-" } else { "" };
+        let exact = exact.abs();
+        let whole = exact.to_integer();
+        if !is_power_of(exact.denom(), radix) {
+            let ratio = |n: &BigInt| Number::Int(n.clone()).to_string_radix(radix);
+            return format!("{}{}/{}", sign, ratio(exact.numer()), ratio(exact.denom()));
+        }
+        let mut rest = exact.fract();
+        let mut digits = vec![];
+        while !rest.is_zero() {
+            rest *= BigInt::from(radix);
+            digits.push(rest.to_integer());
+            rest = rest.fract();
+        }
+        if radix == 60 {
+            let groups: String = digits.iter().map(|it| format!(":{:02}", it)).collect();
+            return format!("{}{}{}", sign, whole, groups);
+        }
+        let prefix = match radix {
+            2 => "0b".to_owned(),
+            8 => "0o".to_owned(),
+            10 => "".to_owned(),
+            16 => "0x".to_owned(),
+            _ => format!("{}#", radix),
+        };
+        let mut text = format!("{}{}{}", sign, prefix, whole.to_str_radix(radix));
+        if !digits.is_empty() {
+            text.push('.');
+            text.extend(digits.iter().map(|it| it.to_str_radix(radix)));
+        }
+        text
+    }
+
     pub fn try_into_int(&self) -> Self {
         match self {
             Number::Rat(r) if *r.denom() == BigInt::one() => Number::Int(r.numer().clone()),
@@ -111,14 +180,18 @@ impl Number {
         }
     }
     pub fn f64_or_nan(&self) -> f64 {
-        self.to_f64().unwrap_or(std::f64::NAN)
+        self.to_f64().unwrap_or(f64::NAN)
     }
-    pub fn is_nan(&self) -> bool {
+    pub fn type_name(&self) -> &'static str {
         match self {
-            Self::Flt(n) if n.is_nan() => true,
-            _ => false,
+            Number::Int(_) => "integer",
+            Number::Rat(_) => "rational",
+            Number::Flt(_) => "float",
         }
     }
+    pub fn is_nan(&self) -> bool {
+        matches!(self, Self::Flt(n) if n.is_nan())
+    }
 }
 
 impl Add for Number {
@@ -166,8 +239,8 @@ impl Mul for Number {
 impl Div for Number {
     type Output = Number;
     fn div(self, rhs: Self) -> Self::Output {
-        if (&rhs).is_zero() {
-            eprintln!("Attempted to divide {} by zero!", self);
+        if rhs.is_zero() {
+            // Not printed: the REPL hint divides on every keystroke, and NaN already says it
             return Self::Flt(f64::NAN);
         }
         match (self, rhs) {
@@ -187,17 +260,13 @@ impl Pow<Number> for Number {
     fn pow(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
             (Number::Flt(x), Number::Flt(y)) => Number::Flt(x.powf(y)),
-            (Number::Flt(x), Number::Int(y)) => {
-                Number::Flt(x.powi(y.to_i32().unwrap_or(std::i32::MIN)))
-            }
-            (Number::Flt(x), Number::Rat(y)) => {
-                Number::Flt(x.powf(y.to_f64().unwrap_or(std::f64::NAN)))
-            }
+            (Number::Flt(x), Number::Int(y)) => Number::Flt(x.powi(y.to_i32().unwrap_or(i32::MIN))),
+            (Number::Flt(x), Number::Rat(y)) => Number::Flt(x.powf(y.to_f64().unwrap_or(f64::NAN))),
             (x, Number::Flt(y)) => Number::Flt(x.f64_or_nan().powf(y)),
             (Number::Rat(x), Number::Rat(y)) => Number::Flt(
                 x.to_f64()
-                    .unwrap_or(std::f64::NAN)
-                    .powf(y.to_f64().unwrap_or(std::f64::NAN)),
+                    .unwrap_or(f64::NAN)
+                    .powf(y.to_f64().unwrap_or(f64::NAN)),
             ),
             (Number::Rat(x), Number::Int(y)) => Number::Rat(Ratio::new(
                 x.numer().pow(y.to_u32().unwrap_or(0)),
@@ -206,8 +275,8 @@ impl Pow<Number> for Number {
             .try_into_int(),
             (Number::Int(x), Number::Rat(y)) => Number::Flt(
                 x.to_f64()
-                    .unwrap_or(std::f64::NAN)
-                    .powf(y.to_f64().unwrap_or(std::f64::NAN)),
+                    .unwrap_or(f64::NAN)
+                    .powf(y.to_f64().unwrap_or(f64::NAN)),
             ),
             (Number::Int(x), Number::Int(y)) => {
                 let mut it = Number::Int(x.pow(y.abs().to_biguint().unwrap()));
@@ -225,8 +294,8 @@ impl Rem for Number {
 
     fn rem(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
-            (Number::Flt(x), y) => Number::Flt(x % y.to_f64().unwrap_or(std::f64::NAN)),
-            (x, Number::Flt(y)) => Number::Flt(x.to_f64().unwrap_or(std::f64::NAN) % y),
+            (Number::Flt(x), y) => Number::Flt(x % y.to_f64().unwrap_or(f64::NAN)),
+            (x, Number::Flt(y)) => Number::Flt(x.to_f64().unwrap_or(f64::NAN) % y),
             (Number::Rat(x), Number::Rat(y)) => Number::Rat(x % y).try_into_int(),
             (Number::Rat(x), Number::Int(y)) => Number::Rat(x % y).try_into_int(),
             (Number::Int(x), Number::Rat(y)) => Number::Rat(y % x).try_into_int(),
@@ -243,6 +312,91 @@ impl Neg for Number {
     }
 }
 
+// Bit operations, on integers as if they were in two's complement, and NaN
+// for anything else
+fn bitwise(x: Number, y: Number, f: fn(BigInt, BigInt) -> BigInt) -> Number {
+    match (x.try_into_int(), y.try_into_int()) {
+        (Number::Int(x), Number::Int(y)) => Number::Int(f(x, y)),
+        _ => Number::Flt(f64::NAN),
+    }
+}
+
+impl BitAnd for Number {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        bitwise(self, rhs, |x, y| x & y)
+    }
+}
+
+impl BitOr for Number {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        bitwise(self, rhs, |x, y| x | y)
+    }
+}
+
+impl BitXor for Number {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        bitwise(self, rhs, |x, y| x ^ y)
+    }
+}
+
+impl Not for Number {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        bitwise(self, Number::zero(), |x, _| !x)
+    }
+}
+
+// Shifts by more than this many bits are NaN
+const MAX_SHIFT: usize = 1 << 20;
+
+impl Shl for Number {
+    type Output = Self;
+
+    fn shl(self, rhs: Self) -> Self::Output {
+        match rhs.try_into_int() {
+            Number::Int(n) if !n.is_negative() && n <= BigInt::from(MAX_SHIFT) => {
+                bitwise(self, Number::Int(n), |x, n| {
+                    x << n.to_usize().unwrap_or_default()
+                })
+            }
+            _ => Number::Flt(f64::NAN),
+        }
+    }
+}
+
+impl Shr for Number {
+    type Output = Self;
+
+    // Rounds towards minus infinity, so that -1 >> 1 is -1
+    fn shr(self, rhs: Self) -> Self::Output {
+        match rhs.try_into_int() {
+            Number::Int(n) if !n.is_negative() && n <= BigInt::from(MAX_SHIFT) => {
+                bitwise(self, Number::Int(n), |x, n| {
+                    x >> n.to_usize().unwrap_or_default()
+                })
+            }
+            _ => Number::Flt(f64::NAN),
+        }
+    }
+}
+
+impl Number {
+    // The number of 1 bits of an integer >= 0, NaN otherwise
+    pub fn popcount(self) -> Self {
+        match self.try_into_int() {
+            Number::Int(n) if !n.is_negative() => Number::from(n.magnitude().count_ones()),
+            _ => Number::Flt(f64::NAN),
+        }
+    }
+}
+
 impl One for Number {
     fn one() -> Self {
         Number::Int(BigInt::one())
@@ -1,6 +1,13 @@
-use num::Num;
+use num::rational::Ratio;
+use num::{BigInt, Num};
 
-use crate::{env::Env, number::Number, token::*};
+use crate::{
+    env::{Env, Variable},
+    number::Number,
+    time,
+    token::*,
+    value::Value,
+};
 
 pub type Parsed<T> = Result<T, String>;
 type Expression<'a> = Vec<Token<'a>>;
@@ -9,6 +16,8 @@ type Expression<'a> = Vec<Token<'a>>;
 pub enum Stmt<'a> {
     Expr(Expression<'a>),
     Assignment(Expression<'a>, Expression<'a>),
+    // name := body, where body is kept as text and re-evaluated on each use
+    Definition(&'a str, &'a str, Expression<'a>),
 }
 
 impl<'a> Stmt<'a> {
@@ -16,55 +25,148 @@ impl<'a> Stmt<'a> {
         match self {
             Self::Assignment(_, it) => it,
             Self::Expr(it) => it,
+            Self::Definition(_, _, it) => it,
         }
     }
 }
 
 pub fn stmt<'a>(text: &'a str, env: &Env) -> Parsed<Stmt<'a>> {
-    let mut tokens = vec![];
-
+    let text = strip_comment(text);
+    if let Some((name, body)) = text.split_once(":=") {
+        return definition(name, body, env);
+    }
     let mut sides = text.split('=');
-    let mut lhs = sides.next().expect("no =?");
+    let lhs = sides.next().expect("no =?");
     let mut column = 1;
-    while !lhs.is_empty() {
-        let token = token(lhs, env, &mut column)?;
-        tokens.push(token.clone());
-        lhs = &lhs[token.lexeme.len()..];
-    }
-    if let Some(mut rhs) = sides.next() {
+    let left_tokens = tokens(lhs, env, &mut column)?;
+    if let Some(rhs) = sides.next() {
         column += 1;
-        let mut right_tokens = vec![];
-        while !rhs.is_empty() {
-            let token = token(rhs, env, &mut column)?;
-            right_tokens.push(token.clone());
-            rhs = &rhs[token.lexeme.len()..];
-        }
-        Ok(Stmt::Assignment(tokens, right_tokens))
+        let right_tokens = tokens(rhs, env, &mut column)?;
+        Ok(Stmt::Assignment(left_tokens, right_tokens))
     } else {
-        Ok(Stmt::Expr(tokens))
+        Ok(Stmt::Expr(left_tokens))
+    }
+}
+
+// The statements in text, split on newlines and `;` outside of quotes, and
+// whether each ended with `;`
+pub fn statements(text: &str) -> Vec<(&str, bool)> {
+    let mut result = vec![];
+    for line in text.lines() {
+        let line = strip_comment(line);
+        let (mut start, mut quoted) = (0, false);
+        for (i, c) in line.char_indices() {
+            match c {
+                '"' => quoted = !quoted,
+                ';' if !quoted => {
+                    result.push((&line[start..i], true));
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        result.push((&line[start..], false));
+    }
+    result.retain(|(part, _)| !part.trim().is_empty());
+    result
+}
+
+// Everything before a `#` that starts a comment, rather than a number in
+// another base such as 36#zz
+pub fn strip_comment(text: &str) -> &str {
+    let is_radix = |i: usize| {
+        let start = text[..i]
+            .char_indices()
+            .rfind(|(_, c)| !c.is_ascii_digit())
+            .map_or(0, |(j, c)| j + c.len_utf8());
+        let before = text[..start].chars().next_back();
+        !before.is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '.')
+            && radix_prefix(&text[start..]).is_some()
+    };
+    let end = text
+        .match_indices('#')
+        .map(|(i, _)| i)
+        .find(|i| !is_radix(*i));
+    &text[..end.unwrap_or(text.len())]
+}
+
+fn definition<'a>(name: &'a str, body: &'a str, env: &Env) -> Parsed<Stmt<'a>> {
+    let mut column = 1;
+    let lhs = tokens(name, env, &mut column)?;
+    let name = match lhs
+        .iter()
+        .filter(|it| it.ttype != TokenType::Space)
+        .collect::<Vec<_>>()[..]
+    {
+        [Token {
+            ttype: TokenType::Identifier,
+            lexeme,
+            ..
+        }] => *lexeme,
+        _ => {
+            return Err(format!(
+                "Expected a name before ':=', found '{}'",
+                name.trim()
+            ))
+        }
+    };
+    column += 2;
+    let rhs = tokens(body, env, &mut column)?;
+    if body.trim().is_empty() {
+        return Err(format!("Expected an expression after '{} :='", name));
+    }
+    Ok(Stmt::Definition(name, body.trim(), rhs))
+}
+
+// Tokens for as much of text as can be lexed, and what's left after them,
+// e.g. to highlight a line that's still being typed
+pub fn lex_prefix<'a>(mut text: &'a str, env: &Env) -> (Expression<'a>, &'a str) {
+    let mut tokens = vec![];
+    let mut column = 1;
+    while let Ok(token) = token(text, env, &mut column) {
+        text = &text[token.lexeme.len()..];
+        tokens.push(token);
+    }
+    (tokens, text)
+}
+
+fn tokens<'a>(mut text: &'a str, env: &Env, column: &mut usize) -> Parsed<Expression<'a>> {
+    let mut tokens = vec![];
+    while !text.is_empty() {
+        let token = token(text, env, column)?;
+        text = &text[token.lexeme.len()..];
+        tokens.push(token);
     }
+    Ok(tokens)
 }
 
-fn token<'a>(text: &'a str, _env: &Env, column: &mut usize) -> Parsed<Token<'a>> {
+fn token<'a>(text: &'a str, env: &Env, column: &mut usize) -> Parsed<Token<'a>> {
     match text.chars().next().ok_or("Tried to parse empty token")? {
-        c if c.is_ascii_digit() => number(text, column),
-        c if c.is_ascii_alphabetic() => identifier(text, column),
-        '-' => symbol(text, column),
+        c if c.is_ascii_digit() => number(text, env, column),
+        c if c.is_alphabetic() || c == '_' => identifier(text, env, column),
+        c if SUPERSCRIPTS.contains(c) => superscript(text, column),
+        '$' if text[1..].starts_with(|c: char| c.is_ascii_digit()) => history(text, column),
+        '-' => operator(text, env, column),
         ' ' => spaces(text, column),
         '\n' => newline(text, column),
-        '[' => lbracket(text, column),
-        ']' => rbracket(text, column),
+        '[' | '{' => lbracket(text, column),
+        ']' | '}' => rbracket(text, column),
         '(' => lparen(text, column),
         ')' => rparen(text, column),
         ',' => comma(text, column),
         ';' => semicolon(text, column),
         '#' => comment(text, column),
-        c if SYMBOLS.contains(c) => symbol(text, column),
+        '"' => quoted(text, column),
+        c if SYMBOLS.contains(c) => operator(text, env, column),
         c => Err(format!("Can't parse '{}'", c)),
     }
 }
 
-pub const SYMBOLS: &str = "!@$%^&*|\"';,./+-=";
+pub const SYMBOLS: &str = "!@$%^&*|\"';,./+-=<>~×÷−√∛·";
+const SUPERSCRIPTS: &str = "⁻⁰¹²³⁴⁵⁶⁷⁸⁹";
+const SUBSCRIPTS: &str = "₀₁₂₃₄₅₆₇₈₉";
+// What operators defined with `:infix` can be made of
+pub const OPERATORS: &str = "!@$%^&*|/+-<>~";
 
 fn char<'a>(
     expected: char,
@@ -103,12 +205,15 @@ fn newline<'a>(text: &'a str, column: &mut usize) -> Parsed<Token<'a>> {
     char('\n', TokenType::Newline, text, column)
 }
 
+// Lists are written in either [] or {}
 fn lbracket<'a>(text: &'a str, column: &mut usize) -> Parsed<Token<'a>> {
-    char('[', TokenType::LBracket, text, column)
+    let c = text.chars().next().ok_or("Tried to parse empty token")?;
+    char(c, TokenType::LBracket, text, column)
 }
 
 fn rbracket<'a>(text: &'a str, column: &mut usize) -> Parsed<Token<'a>> {
-    char(']', TokenType::RBracket, text, column)
+    let c = text.chars().next().ok_or("Tried to parse empty token")?;
+    char(c, TokenType::RBracket, text, column)
 }
 
 fn lparen<'a>(text: &'a str, column: &mut usize) -> Parsed<Token<'a>> {
@@ -119,87 +224,291 @@ fn rparen<'a>(text: &'a str, column: &mut usize) -> Parsed<Token<'a>> {
     char(')', TokenType::RParen, text, column)
 }
 
+fn quoted<'a>(text: &'a str, column: &mut usize) -> Parsed<Token<'a>> {
+    let l = text[1..]
+        .find('"')
+        .ok_or_else(|| format!("Column {}: missing closing '\"'", column))?
+        + 2;
+    let start = *column;
+    *column += text[..l].chars().count();
+    Ok(Token::new(TokenType::Text, &text[..l], start))
+}
+
 fn comment<'a>(text: &'a str, column: &mut usize) -> Parsed<Token<'a>> {
-    let l = text.chars().take_while(|c| *c != '\n').count();
+    let l = text.find('\n').unwrap_or(text.len());
     if l == 0 {
         Err("empty comment".to_owned())
     } else {
-        *column += l;
-        Ok(Token::new(TokenType::Comment, &text[..l], *column - l))
+        let chars = text[..l].chars().count();
+        *column += chars;
+        Ok(Token::new(TokenType::Comment, &text[..l], *column - chars))
     }
 }
 
-fn number<'a>(text: &'a str, column: &mut usize) -> Parsed<Token<'a>> {
-    let mut l: usize = 0;
-    let mut h: usize = 0;
-    let mut radix = 10;
-    if &text[1..1] == "-" {
-        h += 1;
-    }
-    if text[h..].len() > 2 && &text[h..h + 2] == "0x" {
-        h += 2;
-        radix = 16;
-    }
-    if text[h..].len() > 2 && &text[h..h + 2] == "0o" {
-        h += 2;
-        radix = 8;
-    }
-    if text[h..].len() > 2 && &text[h..h + 2] == "0b" {
-        h += 2;
-        radix = 2;
+fn number<'a>(text: &'a str, env: &Env, column: &mut usize) -> Parsed<Token<'a>> {
+    // Dates are read whole, since 2026-10-17 would otherwise be a subtraction
+    if let Some(date) = time::date(text) {
+        let (seconds, length) = date?;
+        let start = *column;
+        *column += length;
+        let ttype = TokenType::Value(Value::Date(seconds));
+        return Ok(Token::new(ttype, &text[..length], start));
     }
+    let (radix, h) = match text.get(..2) {
+        Some("0x") if text.len() > 2 => (16, 2),
+        Some("0o") if text.len() > 2 => (8, 2),
+        Some("0b") if text.len() > 2 => (2, 2),
+        // Unlike the others, 0d needs a digit after it, as in 0deg
+        Some("0d") if text[2..].starts_with(|c: char| c.is_ascii_digit()) => (10, 2),
+        _ => radix_prefix(text).unwrap_or((10, 0)),
+    };
+    let mut l = h;
     for c in text[h..].chars() {
-        // Using c.is_digit(radix) can parse one valid digit as 2 valid ones
-        // e.g. 0b112 is parsed as 0b11 2, which then evals to 6, which is
-        // likely not what the user intended
-        if ((radix <= 10 && c.is_ascii_digit()) || (radix == 16 && c.is_ascii_hexdigit()))
-            || c == '.'
-        {
+        // Underscores separate digits, e.g. 1_000_000, but 2_ is 2*_
+        let separator = c == '_' && l > h && text[l + 1..].starts_with(|c| is_digit(c, radix));
+        if is_digit(c, radix) || c == '.' || separator {
             l += 1;
         } else {
             break;
         }
     }
-    let lexeme = &text[..h + l];
-    if lexeme == "-" {
-        return Err("minus sign not part of negative number".to_owned());
-    }
-    let parsed = Number::from_str_radix(&lexeme[h..], radix);
-    match parsed {
-        Err(_) => Err(format!("failed to parse '{}' in base {}", lexeme, radix)),
-        Ok(_) if l == 0 => Err("empty number".to_owned()),
-        Ok(n) => {
-            *column += l + h;
-            Ok(Token::lit(n, lexeme, *column - l - h))
+    if l == h {
+        return Err("empty number".to_owned());
+    }
+    let digits: String = text[h..l].chars().filter(|c| *c != '_').collect();
+    let mut n = Number::from_str_radix(&digits, radix)
+        .map_err(|_| format!("failed to parse '{}' in base {}", &text[..l], radix))?;
+    let minutes = match h == 0 && !digits.contains('.') {
+        true => sexagesimal(&text[l..], &mut n)?,
+        false => 0,
+    };
+    l += minutes;
+    // 6.022e23, or 0x1p-3 for a power of 2 in hex. 2e and 2e-x are 2*e
+    let markers = match radix {
+        _ if minutes > 0 => "",
+        10 => "eE",
+        16 => "pP",
+        _ => "",
+    };
+    if let Some((exp, length)) = exponent(&text[l..], markers) {
+        n = scale(n, if radix == 16 { 2 } else { 10 }, exp)?;
+        l += length;
+    }
+    if radix == 10 && minutes == 0 && env.settings.si {
+        if let Some((c, exp)) = si_prefix(&text[l..]) {
+            n = scale(n, 10, exp)?;
+            l += c.len_utf8();
         }
     }
+    let lexeme = &text[..l];
+    let start = *column;
+    *column += lexeme.chars().count();
+    Ok(Token::lit(n, lexeme, start))
 }
 
-fn identifier<'a>(text: &'a str, column: &mut usize) -> Parsed<Token<'a>> {
-    let mut l: usize = 0;
-    for c in text.chars() {
-        if c.is_ascii_alphabetic() {
-            l += 1;
-        } else {
+fn is_digit(c: char, radix: u32) -> bool {
+    // Using c.is_digit(radix) can parse one valid digit as 2 valid ones
+    // e.g. 0b112 is parsed as 0b11 2, which then evals to 6, which is
+    // likely not what the user intended
+    (radix <= 10 && c.is_ascii_digit()) || (radix > 10 && c.is_digit(radix))
+}
+
+// The base of a number such as 36#zz, and the length of `36#`, when what
+// follows are digits in that base
+fn radix_prefix(text: &str) -> Option<(u32, usize)> {
+    let (prefix, rest) = text.split_once('#')?;
+    if !(1..=2).contains(&prefix.len()) || !prefix.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let radix = prefix.parse().ok().filter(|it| (2..=36).contains(it))?;
+    let word: Vec<_> = rest
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '.' || *c == '_')
+        .collect();
+    let valid = word.iter().any(|c| c.is_ascii_alphanumeric())
+        && word
+            .iter()
+            .all(|c| c.is_digit(radix) || *c == '.' || *c == '_');
+    valid.then_some((radix, prefix.len() + 1))
+}
+
+// Adds the minutes and seconds of 12:30:15 to the hours in `n`, the same for
+// angles, and returns the length of `:30:15`
+fn sexagesimal(text: &str, n: &mut Number) -> Parsed<usize> {
+    let mut l = 0;
+    let mut place = 0;
+    while text[l..].starts_with(':') && text[l + 1..].starts_with(|c: char| c.is_ascii_digit()) {
+        let group = &text[l + 1..];
+        let length = group
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || *c == '.')
+            .count();
+        let value = Number::from_str_radix(&group[..length], 10)
+            .map_err(|_| format!("failed to parse '{}'", &group[..length]))?;
+        if value >= Number::from(60) {
+            return Err(format!(
+                "Expected less than 60 after ':', found '{}'",
+                &group[..length]
+            ));
+        }
+        place -= 1;
+        *n = n.clone() + scale(value, 60, place)?;
+        l += 1 + length;
+        if group[..length].contains('.') {
             break;
         }
     }
-    if l == 0 {
-        return Err("empty identifier".to_owned());
+    Ok(l)
+}
+
+// The exponent at the start of `text`, e.g. `e-9`, and its length
+fn exponent(text: &str, markers: &str) -> Option<(i32, usize)> {
+    let rest = text.strip_prefix(|c| markers.contains(c))?;
+    let sign = usize::from(rest.starts_with(['+', '-']));
+    let digits = rest[sign..]
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .count();
+    if digits == 0 {
+        return None;
     }
-    *column += l;
-    Ok(Token {
-        ttype: TokenType::Identifier,
-        lexeme: &text[..l],
-        column: *column - l,
+    let exp = rest[..sign + digits].parse().ok()?;
+    Some((exp, 1 + sign + digits))
+}
+
+// Multiples written with a suffix when `:set si on`, e.g. 4.7k, 10M, 3n
+const SI_PREFIXES: [(char, i32); 11] = [
+    ('f', -15),
+    ('p', -12),
+    ('n', -9),
+    ('u', -6),
+    ('µ', -6),
+    ('m', -3),
+    ('k', 3),
+    ('M', 6),
+    ('G', 9),
+    ('T', 12),
+    ('P', 15),
+];
+
+// The SI prefix at the start of `text`, unless it starts a name, e.g. 2min or 2pi
+fn si_prefix(text: &str) -> Option<(char, i32)> {
+    let mut chars = text.chars();
+    let first = chars.next()?;
+    let prefix = SI_PREFIXES.into_iter().find(|(c, _)| *c == first)?;
+    match chars.next() {
+        Some(c) if c.is_alphanumeric() || c == '_' => None,
+        _ => Some(prefix),
+    }
+}
+
+// n * base^exp, exactly unless n is a float
+fn scale(n: Number, base: u32, exp: i32) -> Parsed<Number> {
+    if exp.unsigned_abs() > MAX_EXPONENT {
+        return Err(format!("exponent {} is too large", exp));
+    }
+    Ok(match n {
+        Number::Flt(x) => Number::Flt(x * f64::from(base).powi(exp)),
+        n => (n * Number::Rat(Ratio::from(BigInt::from(base)).pow(exp))).try_into_int(),
     })
 }
 
+const MAX_EXPONENT: u32 = 100_000;
+
+// Whether `text` can be used as a name in `env`, e.g. `v_max`, `x1`, `θ` or `θ₀`,
+// but not `cos2`
+pub fn is_name(text: &str, env: &Env) -> bool {
+    let mut column = 0;
+    text.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && identifier(text, env, &mut column).is_ok_and(|it| it.lexeme == text)
+}
+
+// Names are ASCII words such as `v_max`, or single letters such as `θ`, followed
+// by digits, subscripts or `_` and more of the name, e.g. `x1`, `θ₀` or `θ_max`.
+// Digits right after the name of a function are its argument instead, e.g. `cos2pi`.
+fn identifier<'a>(text: &'a str, env: &Env, column: &mut usize) -> Parsed<Token<'a>> {
+    let mut chars = text.char_indices();
+    let (_, first) = chars.next().ok_or("empty identifier")?;
+    let mut word = first.is_ascii();
+    let mut end = text.len();
+    for (i, c) in chars {
+        let digit = c.is_ascii_digit() || SUBSCRIPTS.contains(c);
+        if digit && matches!(env.get(&text[..i]), Some(Variable::Function(_))) {
+            end = i;
+            break;
+        }
+        word |= c == '_';
+        if !(digit || c == '_' || (word && c.is_ascii_alphabetic())) {
+            end = i;
+            break;
+        }
+    }
+    let lexeme = &text[..end];
+    *column += lexeme.chars().count();
+    Ok(Token::new(
+        TokenType::Identifier,
+        lexeme,
+        *column - lexeme.chars().count(),
+    ))
+}
+
+// Exponents such as the ² in x², or ⁻¹
+fn superscript<'a>(text: &'a str, column: &mut usize) -> Parsed<Token<'a>> {
+    let end = text
+        .find(|c: char| !SUPERSCRIPTS.contains(c))
+        .unwrap_or(text.len());
+    let lexeme = &text[..end];
+    let digits: String = lexeme
+        .chars()
+        .map(|c| match c {
+            '⁻' => '-',
+            '¹' => '1',
+            '²' => '2',
+            '³' => '3',
+            c => char::from_digit(c as u32 - '⁰' as u32, 10).unwrap_or(c),
+        })
+        .collect();
+    let n = Number::from_str_radix(&digits, 10)
+        .map_err(|_| format!("Can't parse exponent '{}'", lexeme))?;
+    *column += lexeme.chars().count();
+    Ok(Token::new(
+        TokenType::Superscript(n),
+        lexeme,
+        *column - lexeme.chars().count(),
+    ))
+}
+
+// $1, $2, ... refer to earlier results
+fn history<'a>(text: &'a str, column: &mut usize) -> Parsed<Token<'a>> {
+    let l = 1 + text[1..].chars().take_while(|c| c.is_ascii_digit()).count();
+    if l == 1 {
+        return Err("expected a number after $".to_owned());
+    }
+    *column += l;
+    Ok(Token::new(TokenType::Identifier, &text[..l], *column - l))
+}
+
+// The longest operator known to `env` at the start of `text`, e.g. `**` in `2**-3`,
+// or else a single symbol.
+fn operator<'a>(text: &'a str, env: &Env, column: &mut usize) -> Parsed<Token<'a>> {
+    let run = text.len() - text.trim_start_matches(|c| OPERATORS.contains(c)).len();
+    match (2..=run)
+        .rev()
+        .find(|l| matches!(env.get(&text[..*l]), Some(Variable::Function(_))))
+    {
+        Some(l) => {
+            *column += l;
+            Ok(Token::sym(&text[..l], *column - l))
+        }
+        None => symbol(text, column),
+    }
+}
+
 fn symbol<'a>(text: &'a str, column: &mut usize) -> Parsed<Token<'a>> {
     let actual = text.chars().next().ok_or("there should be a char here")?;
     if SYMBOLS.contains(actual) {
         *column += 1;
-        Ok(Token::sym(&text[..1], *column - 1))
+        Ok(Token::sym(&text[..actual.len_utf8()], *column - 1))
     } else {
         Err(format!("expected binary, found: {}", actual))
     }
@@ -270,6 +579,67 @@ mod test {
         )
     }
 
+    #[test]
+    fn _definition() {
+        assert_eq!(
+            stmt("area := w*h", &env::Env::prelude()),
+            Ok(Stmt::Definition(
+                "area",
+                "w*h",
+                vec![
+                    Token::space(8),
+                    Token::new(TokenType::Identifier, "w", 9),
+                    Token::sym("*", 10),
+                    Token::new(TokenType::Identifier, "h", 11),
+                ]
+            ))
+        );
+        assert!(stmt("2x := 4", &env::Env::prelude()).is_err());
+        assert!(stmt("x :=  ", &env::Env::prelude()).is_err());
+    }
+
+    #[test]
+    fn _comment() {
+        assert_eq!(
+            stmt("x=6 # x = 7", &env::Env::prelude()).unwrap(),
+            Stmt::Assignment(
+                vec![Token::new(TokenType::Identifier, "x", 1)],
+                vec![Token::lit(Number::from(6), "6", 3), Token::space(4)]
+            )
+        );
+    }
+
+    #[test]
+    fn _statements() {
+        assert_eq!(
+            statements("a = 3; b = a^2;;a + b # c; d\n x;"),
+            vec![
+                ("a = 3", true),
+                (" b = a^2", true),
+                ("a + b ", false),
+                (" x", true)
+            ]
+        );
+        assert_eq!(statements(" # c"), vec![]);
+        assert_eq!(
+            statements("load(\"a;b.csv\"); 1"),
+            vec![("load(\"a;b.csv\")", true), (" 1", false)]
+        );
+    }
+
+    #[test]
+    fn _history() {
+        test_expr(
+            "$12_ v_max",
+            vec![
+                Token::new(TokenType::Identifier, "$12", 1),
+                Token::new(TokenType::Identifier, "_", 4),
+                Token::space(5),
+                Token::new(TokenType::Identifier, "v_max", 6),
+            ],
+        );
+    }
+
     #[test]
     fn _parse() {
         let expected = Ok(Stmt::Expr(vec![
@@ -336,4 +706,115 @@ mod test {
             ]))
         );
     }
+
+    #[test]
+    fn _unicode() {
+        let id = |lexeme, column| Token::new(TokenType::Identifier, lexeme, column);
+        test_expr("x1", vec![id("x1", 1)]);
+        test_expr("v_max", vec![id("v_max", 1)]);
+        test_expr("θ₀", vec![id("θ₀", 1)]);
+        test_expr(
+            "cos2π",
+            vec![
+                id("cos", 1),
+                Token::lit(Number::from(2), "2", 4),
+                id("π", 5),
+            ],
+        );
+        test_expr(
+            "2×x²",
+            vec![
+                Token::lit(Number::from(2), "2", 1),
+                Token::sym("×", 2),
+                id("x", 3),
+                Token::new(TokenType::Superscript(Number::from(2)), "²", 4),
+            ],
+        );
+        test_expr(
+            "x⁻¹",
+            vec![
+                id("x", 1),
+                Token::new(TokenType::Superscript(Number::from(-1)), "⁻¹", 2),
+            ],
+        );
+    }
+
+    #[test]
+    fn _is_name() {
+        let env = Env::prelude();
+        assert!(is_name("θ₀", &env));
+        assert!(is_name("v_max", &env));
+        assert!(!is_name("cos2", &env));
+        assert!(!is_name("2x", &env));
+    }
+
+    #[test]
+    fn _exponent() {
+        let ratio = |n: i64, d: i64| Number::Rat(Ratio::new(n.into(), d.into()));
+        test_expr("1e-9", vec![Token::lit(ratio(1, 1_000_000_000), "1e-9", 1)]);
+        test_expr("2.5E+3", vec![Token::lit(Number::from(2500), "2.5E+3", 1)]);
+        test_expr("1_000", vec![Token::lit(Number::from(1000), "1_000", 1)]);
+        test_expr(
+            "0xFF_FF",
+            vec![Token::lit(Number::from(0xffff), "0xFF_FF", 1)],
+        );
+        test_expr("0x1p-3", vec![Token::lit(ratio(1, 8), "0x1p-3", 1)]);
+        test_expr(
+            "2e",
+            vec![
+                Token::lit(Number::from(2), "2", 1),
+                Token::new(TokenType::Identifier, "e", 2),
+            ],
+        );
+        test_expr(
+            "2_",
+            vec![
+                Token::lit(Number::from(2), "2", 1),
+                Token::new(TokenType::Identifier, "_", 2),
+            ],
+        );
+        assert!(stmt("1e1000000", &env::Env::prelude()).is_err());
+    }
+
+    #[test]
+    fn _si() {
+        let mut env = env::Env::prelude();
+        assert_eq!(
+            stmt("4k", &env),
+            Ok(Stmt::Expr(vec![
+                Token::lit(Number::from(4), "4", 1),
+                Token::new(TokenType::Identifier, "k", 2),
+            ]))
+        );
+        env.settings.si = true;
+        assert_eq!(
+            stmt("4.7k", &env),
+            Ok(Stmt::Expr(vec![Token::lit(Number::from(4700), "4.7k", 1)]))
+        );
+        assert_eq!(
+            stmt("2pi", &env),
+            Ok(Stmt::Expr(vec![
+                Token::lit(Number::from(2), "2", 1),
+                Token::new(TokenType::Identifier, "pi", 2),
+            ]))
+        );
+    }
+
+    #[test]
+    fn _radix() {
+        test_expr("36#zz", vec![Token::lit(Number::from(1295), "36#zz", 1)]);
+        test_expr("0d19", vec![Token::lit(Number::from(19), "0d19", 1)]);
+        test_expr(
+            "12:30",
+            vec![Token::lit(
+                Number::Rat(Ratio::new(25.into(), 2.into())),
+                "12:30",
+                1,
+            )],
+        );
+        assert!(stmt("12:75", &env::Env::prelude()).is_err());
+        assert_eq!(strip_comment("16#ff # max"), "16#ff ");
+        assert_eq!(strip_comment("5#comment"), "5");
+        assert_eq!(strip_comment("x2#ff"), "x2");
+    }
 }
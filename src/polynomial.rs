@@ -1,55 +1,152 @@
 use std::{
-    fmt::Debug,
-    iter::{repeat, zip},
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    iter::zip,
     ops::*,
 };
 
-use num::{One, Zero};
+use num::{rational::Ratio, One, Signed, ToPrimitive, Zero};
 
 use crate::{
-    function::{ADD, MUL, POW, SUB},
+    function::{ADD, DIV, MUL, POW, SUB},
     number::{self, Number},
     parser::Parsed,
-    s::{eval, S},
+    s::{eval, eval_value, S},
+    stats,
+    value::Value,
 };
 
-#[derive(Debug, Clone)]
-pub struct Polynomial<'a> {
-    pub unknown: &'a str,
-    coefs: Vec<Number>,
+// The power of each variable in a term, e.g. {x: 2, y: 1} for x²y
+type Monomial = BTreeMap<String, u32>;
+
+// A polynomial in any number of variables, e.g. x²y - 3y + 1/2
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Polynomial {
+    // Only the terms whose coefficient isn't 0
+    terms: BTreeMap<Monomial, Number>,
 }
 
-// The 1st element gives the 0th grade coefficient
-impl<'a> Polynomial<'a> {
-    pub fn new(unknown: &'a str, n: Number) -> Self {
-        Self {
-            unknown,
-            coefs: vec![n],
+impl Polynomial {
+    pub fn constant(n: Number) -> Self {
+        let mut result = Self::default();
+        result.insert(Monomial::new(), n);
+        result
+    }
+
+    pub fn variable(name: &str) -> Self {
+        let mut result = Self::default();
+        result.insert(Monomial::from([(name.to_owned(), 1)]), Number::one());
+        result
+    }
+
+    // The coefficients from the constant one up
+    pub fn with_coefs(unknown: &str, coefs: Vec<Number>) -> Self {
+        let mut result = Self::default();
+        for (k, c) in coefs.into_iter().enumerate() {
+            result.insert(power(unknown, k as u32), c);
         }
+        result
     }
-    fn set_unknown(&mut self, name: &'a str) {
-        self.unknown = if self.unknown == name || name.is_empty() {
-            self.unknown
-        } else if self.unknown.is_empty() {
-            name
-        } else {
-            panic!("two different unknowns: {}, {}", self.unknown, name)
+
+    // Adds c times the monomial, dropping terms that cancel out
+    fn insert(&mut self, m: Monomial, c: Number) {
+        let c = match self.terms.remove(&m) {
+            Some(old) => old + c,
+            None => c,
         };
+        if !c.is_zero() {
+            self.terms.insert(m, c);
+        }
+    }
+
+    // Each term's variables with their powers, and its coefficient
+    pub fn terms(&self) -> impl Iterator<Item = (&BTreeMap<String, u32>, &Number)> {
+        self.terms.iter()
     }
+
+    pub fn is_zero(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    // The value of a polynomial without variables
+    pub fn as_constant(&self) -> Option<Number> {
+        match &self.terms.iter().collect::<Vec<_>>()[..] {
+            [] => Some(Number::zero()),
+            [(m, c)] if m.is_empty() => Some((*c).clone()),
+            _ => None,
+        }
+    }
+
+    // The name of a polynomial that's only a variable, e.g. x
+    pub fn as_variable(&self) -> Option<&str> {
+        match &self.terms.iter().collect::<Vec<_>>()[..] {
+            [(m, c)] if c.is_one() => match &m.iter().collect::<Vec<_>>()[..] {
+                [(x, 1)] => Some(x),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    // In alphabetical order
+    pub fn variables(&self) -> Vec<&str> {
+        let names: BTreeSet<_> = self.terms.keys().flat_map(|m| m.keys()).collect();
+        names.into_iter().map(String::as_str).collect()
+    }
+
+    // The highest sum of powers in a term, None for 0
+    pub fn degree(&self) -> Option<u32> {
+        self.terms.keys().map(|m| m.values().sum()).max()
+    }
+
+    // The coefficients from the constant one up, of a polynomial of at most one
+    // variable
+    pub fn coefs(&self) -> Parsed<Vec<Number>> {
+        match self.variables()[..] {
+            [] | [_] => Ok(self.dense()),
+            _ => Err(format!("{} has more than one variable", self)),
+        }
+    }
+
+    // The coefficients by power, read as if every variable were the same
+    fn dense(&self) -> Vec<Number> {
+        let mut coefs = vec![Number::zero(); self.degree().map_or(1, |it| it as usize + 1)];
+        for (m, c) in &self.terms {
+            coefs[m.values().sum::<u32>() as usize] = c.clone();
+        }
+        coefs
+    }
+
+    // Highest degree first, then by the powers of variables in alphabetical
+    // order, e.g. x² + xy + y²
+    fn sorted(&self) -> Vec<(&Monomial, &Number)> {
+        let variables = self.variables();
+        let key = |m: &Monomial| {
+            let powers: Vec<_> = variables.iter().map(|x| m.get(*x).copied()).collect();
+            Reverse((m.values().sum::<u32>(), powers))
+        };
+        let mut terms: Vec<_> = self.terms.iter().collect();
+        terms.sort_by_key(|(m, _)| key(m));
+        terms
+    }
+
     pub fn roots(&self) -> Vec<Number> {
-        let inner = match &self.coefs[..] {
+        let coefs = self.coefs().unwrap_or_default();
+        let inner = match &coefs[..] {
             [] => vec![],
             [_] => vec![],
             [b, a] => vec![-(b.clone() / a.clone())],
             [c, b, a] => {
-                let delta = ((b.clone() * b.clone()) - ((a.clone() * c.clone()) * Number::from(4)))
-                    .f64_or_nan()
-                    .sqrt();
+                let delta = (b.clone() * b.clone()) - ((a.clone() * c.clone()) * Number::from(4));
+                // Exact when the discriminant is the square of a rational
+                let delta = exact_sqrt(&delta)
+                    .unwrap_or_else(|| number::Number::Flt(delta.f64_or_nan().sqrt()));
                 // TODO: complex solutions
                 if !delta.is_nan() {
                     vec![
-                        (-(b.clone() - number::Number::Flt(delta))) / (Number::from(2) * a.clone()),
-                        (-(b.clone() + number::Number::Flt(delta))) / (Number::from(2) * a.clone()),
+                        (-(b.clone() - delta.clone())) / (Number::from(2) * a.clone()),
+                        (-(b.clone() + delta)) / (Number::from(2) * a.clone()),
                     ]
                 } else {
                     vec![]
@@ -61,91 +158,366 @@ impl<'a> Polynomial<'a> {
         // TODO: multiple solutions
         inner
     }
+
+    // The value with every variable equal to x
+    pub fn at(&self, x: &Number) -> Number {
+        let mut coefs = self.dense().into_iter().rev();
+        let first = coefs.next().unwrap_or_else(Number::zero);
+        coefs.fold(first, |acc, c| acc * x.clone() + c)
+    }
+
+    // The variables replaced by `values` in alphabetical order, e.g. p(2, 3)
+    // for x and y, which can be polynomials themselves
+    pub fn substitute(&self, values: &[Polynomial]) -> Parsed<Polynomial> {
+        let variables = self.variables();
+        if variables.len() != values.len() {
+            return Err(format!(
+                "{} takes a value for each of its variables, {}, found {}",
+                self,
+                match variables.len() {
+                    0 => "none".to_owned(),
+                    _ => variables.join(", "),
+                },
+                values.len()
+            ));
+        }
+        let values: BTreeMap<_, _> = zip(variables, values).collect();
+        let mut result = Polynomial::default();
+        for (m, c) in &self.terms {
+            let mut term = Polynomial::constant(c.clone());
+            for (x, k) in m {
+                term *= &values[x.as_str()].pow(*k);
+            }
+            result += &term;
+        }
+        Ok(result)
+    }
+
+    pub fn pow(&self, n: u32) -> Polynomial {
+        let mut result = Polynomial::constant(Number::one());
+        for _ in 0..n {
+            result *= self;
+        }
+        result
+    }
+
+    // The derivative with respect to x
+    pub fn derive(&self, x: &str) -> Polynomial {
+        let mut result = Polynomial::default();
+        for (m, c) in &self.terms {
+            let Some(&k) = m.get(x) else { continue };
+            let mut m = m.clone();
+            match k {
+                1 => m.remove(x),
+                k => m.insert(x.to_owned(), k - 1),
+            };
+            result.insert(m, c.clone() * Number::from(k));
+        }
+        result
+    }
+
+    // The quotient and remainder of long division, for polynomials of one
+    // variable or by a constant
+    pub fn div_rem(&self, other: &Self) -> Parsed<(Polynomial, Polynomial)> {
+        match other.as_constant() {
+            Some(c) if c.is_zero() => return Err("Can't divide by the polynomial 0".to_owned()),
+            Some(c) => {
+                let scaled = self * &Polynomial::constant(Number::one() / c);
+                return Ok((scaled, Polynomial::default()));
+            }
+            None => {}
+        }
+        let variables: BTreeSet<_> = self
+            .variables()
+            .into_iter()
+            .chain(other.variables())
+            .collect();
+        let x = match variables.into_iter().collect::<Vec<_>>()[..] {
+            [x] => x,
+            ref several => {
+                return Err(format!(
+                    "Can't divide polynomials of several variables, {}",
+                    several.join(", ")
+                ))
+            }
+        };
+        let divisor = other.dense();
+        let mut remainder = self.dense();
+        let mut quotient = vec![Number::zero(); remainder.len().saturating_sub(divisor.len()) + 1];
+        let lead = divisor[divisor.len() - 1].clone();
+        while remainder.len() >= divisor.len() && remainder.iter().any(|it| !it.is_zero()) {
+            let shift = remainder.len() - divisor.len();
+            let factor = remainder[remainder.len() - 1].clone() / lead.clone();
+            for (r, d) in remainder[shift..].iter_mut().zip(&divisor) {
+                *r = r.clone() - factor.clone() * d.clone();
+            }
+            quotient[shift] = factor;
+            // Dropped even when floats leave something behind
+            remainder.pop();
+        }
+        Ok((
+            Polynomial::with_coefs(x, quotient),
+            Polynomial::with_coefs(x, remainder),
+        ))
+    }
+
+    // The greatest common divisor of polynomials of one variable, with a
+    // leading coefficient of 1, by Euclid's algorithm
+    pub fn gcd(&self, other: &Self) -> Parsed<Polynomial> {
+        let (mut a, mut b) = (self.clone(), other.clone());
+        while !b.is_zero() {
+            let (_, remainder) = a.div_rem(&b)?;
+            a = b;
+            b = remainder;
+        }
+        let lead = a.sorted().first().map(|(_, c)| (*c).clone());
+        Ok(match lead {
+            Some(c) => &a * &Polynomial::constant(Number::one() / c),
+            None => a,
+        })
+    }
+
+    // The least-squares polynomial of `degree` through the points, solving
+    // the normal equations exactly when the points are integers or rationals
+    pub fn fit(unknown: &str, xs: &[Number], ys: &[Number], degree: usize) -> Parsed<Self> {
+        if xs.len() != ys.len() {
+            return Err(format!(
+                "Can't fit {} x values to {} y values",
+                xs.len(),
+                ys.len()
+            ));
+        }
+        // The sums of x^k, and of x^k * y
+        let mut powers = vec![Number::zero(); 2 * degree + 1];
+        let mut moments = vec![Number::zero(); degree + 1];
+        for (x, y) in zip(xs, ys) {
+            let mut power = Number::one();
+            for k in 0..powers.len() {
+                powers[k] = powers[k].clone() + power.clone();
+                if k < moments.len() {
+                    moments[k] = moments[k].clone() + power.clone() * y.clone();
+                }
+                power = power * x.clone();
+            }
+        }
+        let rows = (0..=degree)
+            .map(|i| powers[i..=i + degree].to_vec())
+            .collect();
+        let coefs = solve(rows, moments).ok_or_else(|| {
+            format!(
+                "Need at least {} different x values to fit a polynomial of degree {}",
+                degree + 1,
+                degree
+            )
+        })?;
+        let coefs = coefs.into_iter().map(|it| it.try_into_int()).collect();
+        Ok(Self::with_coefs(unknown, coefs))
+    }
+
+    // The coefficient of determination of the points, 1 when they are all
+    // on the polynomial, NaN when the ys are all the same
+    pub fn r2(&self, xs: &[Number], ys: &[Number]) -> Number {
+        let mean = stats::mean(ys);
+        let square = |d: Number| d.clone() * d;
+        let residuals = zip(xs, ys).map(|(x, y)| square(y.clone() - self.at(x)));
+        let total = ys.iter().map(|y| square(y.clone() - mean.clone()));
+        let residuals = residuals.fold(Number::zero(), |a, b| a + b);
+        let total = total.fold(Number::zero(), |a, b| a + b);
+        if total.is_zero() {
+            return Number::Flt(f64::NAN);
+        }
+        (Number::one() - residuals / total).try_into_int()
+    }
+
+    // A polynomial of one variable as an expression of `x`, e.g. 3/2*x^2 - 1,
+    // so that it can be evaluated or solved like one that was typed
+    pub fn expr<'a>(&self, x: S<'a>) -> S<'a> {
+        let term = |k: u32, c: Number| {
+            let power = match k {
+                0 => return S::Var(c),
+                1 => x.clone(),
+                k => S::Fun(POW, vec![x.clone(), S::Var(Number::from(k))]),
+            };
+            match c.is_one() {
+                true => power,
+                false => S::Fun(MUL, vec![S::Var(c), power]),
+            }
+        };
+        let mut terms = self
+            .sorted()
+            .into_iter()
+            .map(|(m, c)| (m.values().sum(), c));
+        let Some((k, c)) = terms.next() else {
+            return S::Var(Number::zero());
+        };
+        terms.fold(term(k, c.clone()), |sum, (k, c)| match c.is_negative() {
+            true => S::Fun(SUB, vec![sum, term(k, -c.clone())]),
+            false => S::Fun(ADD, vec![sum, term(k, c.clone())]),
+        })
+    }
+}
+
+fn power(x: &str, k: u32) -> Monomial {
+    match k {
+        0 => Monomial::new(),
+        k => Monomial::from([(x.to_owned(), k)]),
+    }
 }
 
-impl<'a> Add<Self> for &Polynomial<'a> {
-    type Output = Polynomial<'a>;
+fn superscript(k: u32) -> String {
+    const DIGITS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+    k.to_string()
+        .chars()
+        .map(|it| DIGITS[it.to_digit(10).unwrap_or(0) as usize])
+        .collect()
+}
+
+// Written so that it reads back as the same polynomial, e.g. 3x²·y - 1/2·x,
+// as integers next to a variable multiply it
+impl fmt::Display for Polynomial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let terms = self.sorted();
+        if terms.is_empty() {
+            return write!(f, "0");
+        }
+        for (i, (m, c)) in terms.into_iter().enumerate() {
+            let c = match (i, c.is_negative()) {
+                (0, true) => {
+                    write!(f, "-")?;
+                    -c.clone()
+                }
+                (0, false) => c.clone(),
+                (_, true) => {
+                    write!(f, " - ")?;
+                    -c.clone()
+                }
+                (_, false) => {
+                    write!(f, " + ")?;
+                    c.clone()
+                }
+            };
+            let powers: Vec<_> = m
+                .iter()
+                .map(|(x, k)| match k {
+                    1 => x.clone(),
+                    k => format!("{}{}", x, superscript(*k)),
+                })
+                .collect();
+            let powers = powers.join("·");
+            match c {
+                c if m.is_empty() => write!(f, "{}", c)?,
+                c if c.is_one() => write!(f, "{}", powers)?,
+                Number::Int(n) => write!(f, "{}{}", n, powers)?,
+                c => write!(f, "{}·{}", c, powers)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// The square root of an integer or rational that's a square, e.g. 9/4
+fn exact_sqrt(n: &Number) -> Option<Number> {
+    let r = match n {
+        Number::Int(n) => Ratio::from_integer(n.clone()),
+        Number::Rat(r) => r.clone(),
+        Number::Flt(_) => return None,
+    };
+    if r.is_negative() {
+        return None;
+    }
+    let (numer, denom) = (r.numer().sqrt(), r.denom().sqrt());
+    let square = &numer * &numer == *r.numer() && &denom * &denom == *r.denom();
+    square.then(|| Number::Rat(Ratio::new(numer, denom)).try_into_int())
+}
+
+// The solution of a square system of linear equations, by Gaussian elimination,
+// or None if it has no single solution
+fn solve(mut rows: Vec<Vec<Number>>, mut rhs: Vec<Number>) -> Option<Vec<Number>> {
+    let n = rhs.len();
+    for i in 0..n {
+        // The largest pivot, which only matters for floats
+        let pivot = (i..n).max_by(|a, b| {
+            let (a, b) = (rows[*a][i].abs(), rows[*b][i].abs());
+            a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+        })?;
+        if rows[pivot][i].is_zero() || rows[pivot][i].is_nan() {
+            return None;
+        }
+        rows.swap(i, pivot);
+        rhs.swap(i, pivot);
+        let (above, below) = rows.split_at_mut(i + 1);
+        for (j, row) in (i + 1..n).zip(below) {
+            let factor = row[i].clone() / above[i][i].clone();
+            for (cell, x) in row.iter_mut().zip(&above[i]).skip(i) {
+                *cell = cell.clone() - factor.clone() * x.clone();
+            }
+            rhs[j] = rhs[j].clone() - factor * rhs[i].clone();
+        }
+    }
+    let mut result = vec![Number::zero(); n];
+    for i in (0..n).rev() {
+        let known = (i + 1..n).fold(Number::zero(), |acc, k| {
+            acc + rows[i][k].clone() * result[k].clone()
+        });
+        result[i] = (rhs[i].clone() - known) / rows[i][i].clone();
+    }
+    Some(result)
+}
+
+impl Add<Self> for &Polynomial {
+    type Output = Polynomial;
     fn add(self, other: Self) -> Self::Output {
-        let (longest, shortest) = if self.coefs.len() > other.coefs.len() {
-            (&(self.coefs), &(other.coefs))
-        } else {
-            (&(other.coefs), &(self.coefs))
-        };
-        let coefs = zip(
-            longest.iter(),
-            shortest.iter().chain(repeat(&Number::zero())),
-        )
-        .map(|(x, y)| x.clone() + y.clone())
-        .collect();
-        let mut result = Polynomial { coefs, ..*self };
-        result.set_unknown(other.unknown);
+        let mut result = self.clone();
+        for (m, c) in &other.terms {
+            result.insert(m.clone(), c.clone());
+        }
         result
     }
 }
 
-impl<'a> AddAssign<&Self> for Polynomial<'a> {
+impl AddAssign<&Self> for Polynomial {
     fn add_assign(&mut self, rhs: &Self) {
-        let result = &*self + rhs;
-        self.coefs = result.coefs;
-        self.unknown = result.unknown;
+        *self = &*self + rhs;
     }
 }
 
-impl<'a> Sub<Self> for &Polynomial<'a> {
-    type Output = Polynomial<'a>;
+impl Sub<Self> for &Polynomial {
+    type Output = Polynomial;
     fn sub(self, other: Self) -> Self::Output {
-        let (longest, shortest) = if self.coefs.len() > other.coefs.len() {
-            (&(self.coefs), &(other.coefs))
-        } else {
-            (&(other.coefs), &(self.coefs))
-        };
-        let coefs = zip(
-            longest.iter(),
-            shortest.iter().chain(repeat(&Number::zero())),
-        )
-        .map(|(x, y)| x.clone() - y.clone())
-        .collect();
-        let mut result = Polynomial { coefs, ..*self };
-        result.set_unknown(other.unknown);
-        result
+        self + &-other.clone()
     }
 }
 
-impl<'a> SubAssign<&Self> for Polynomial<'a> {
+impl SubAssign<&Self> for Polynomial {
     fn sub_assign(&mut self, rhs: &Self) {
-        let result = &*self - rhs;
-        self.coefs = result.coefs;
-        self.unknown = result.unknown;
+        *self = &*self - rhs;
     }
 }
 
-impl<'a> Mul<Self> for &Polynomial<'a> {
-    type Output = Polynomial<'a>;
+impl Mul<Self> for &Polynomial {
+    type Output = Polynomial;
     fn mul(self, other: Self) -> Self::Output {
-        let mut result = vec![Number::zero(); self.coefs.len() + other.coefs.len() - 1];
-        for (d1, c1) in self.coefs.iter().enumerate() {
-            for (d2, c2) in other.coefs.iter().enumerate() {
-                result[d1 + d2] = c1.clone() * c2.clone() + result[d1 + d2].clone();
+        let mut result = Polynomial::default();
+        for (m1, c1) in &self.terms {
+            for (m2, c2) in &other.terms {
+                let mut m = m1.clone();
+                for (x, k) in m2 {
+                    *m.entry(x.clone()).or_insert(0) += k;
+                }
+                result.insert(m, c1.clone() * c2.clone());
             }
         }
-        let mut result = Polynomial {
-            coefs: result,
-            ..*self
-        };
-        result.set_unknown(other.unknown);
         result
     }
 }
 
-impl<'a> MulAssign<&Self> for Polynomial<'a> {
+impl MulAssign<&Self> for Polynomial {
     fn mul_assign(&mut self, rhs: &Self) {
-        let result = &*self * rhs;
-        self.coefs = result.coefs;
-        self.unknown = result.unknown;
+        *self = &*self * rhs;
     }
 }
 
-impl<'a> Add<Number> for Polynomial<'a> {
+impl Add<Number> for Polynomial {
     type Output = Self;
     fn add(self, other: Number) -> Self::Output {
         let mut result = self.clone();
@@ -154,75 +526,154 @@ impl<'a> Add<Number> for Polynomial<'a> {
     }
 }
 
-impl<'a> AddAssign<Number> for Polynomial<'a> {
+impl AddAssign<Number> for Polynomial {
     fn add_assign(&mut self, other: Number) {
-        self.coefs[0] = self.coefs[0].clone() + other;
+        self.insert(Monomial::new(), other);
     }
 }
 
-impl<'a> SubAssign<Number> for Polynomial<'a> {
+impl SubAssign<Number> for Polynomial {
     fn sub_assign(&mut self, other: Number) {
-        self.coefs[0] = self.coefs[0].clone() - other;
+        self.insert(Monomial::new(), -other);
     }
 }
 
-impl<'a> Neg for Polynomial<'a> {
+impl Neg for Polynomial {
     type Output = Self;
 
     fn neg(self) -> Self::Output {
-        Self::Output {
-            coefs: self.coefs.iter().map(|x| x.clone().neg()).collect(),
-            ..self
+        Self {
+            terms: self.terms.into_iter().map(|(m, c)| (m, -c)).collect(),
         }
     }
 }
 
-pub fn polynomial<'a>(s: &S<'a>) -> Parsed<Polynomial<'a>> {
+pub fn polynomial(s: &S) -> Parsed<Polynomial> {
     match s {
-        S::Var(n) => Ok(Polynomial::new("", n.clone())),
+        S::Var(n) => Ok(Polynomial::constant(n.clone())),
+        S::Value(Value::Poly(p)) => Ok(p.clone()),
+        S::Value(v) => Ok(Polynomial::constant(v.clone().into_number()?)),
         S::Fun(fun, ss) => {
             if fun == &ADD {
-                let mut result = Polynomial::new("", Number::zero());
+                let mut result = Polynomial::default();
                 for s in ss {
                     result += &polynomial(s)?;
                 }
                 Ok(result)
             } else if fun == &SUB {
-                let mut result = Polynomial::new("", Number::zero());
-                for s in ss {
+                // -x, or x - y - ...
+                let Some((first, rest)) = ss.split_first() else {
+                    return Ok(Polynomial::default());
+                };
+                let mut result = polynomial(first)?;
+                if rest.is_empty() {
+                    return Ok(-result);
+                }
+                for s in rest {
                     result -= &polynomial(s)?;
                 }
                 Ok(result)
             } else if fun == &MUL {
-                let mut result = Polynomial::new("", Number::one());
+                let mut result = Polynomial::constant(Number::one());
                 for s in ss {
                     result *= &polynomial(s)?;
                 }
                 Ok(result)
+            } else if fun == &DIV && ss.len() == 2 {
+                // Only when it divides exactly, e.g. (x^2 - 1)/(x - 1) or x/2
+                let (p, q) = (polynomial(&ss[0])?, polynomial(&ss[1])?);
+                match p.div_rem(&q)? {
+                    (quotient, remainder) if remainder.is_zero() => Ok(quotient),
+                    _ => Err(format!("{} isn't divisible by {}", p, q)),
+                }
             } else if fun == &POW {
                 // TODO: Matrix exponents
-                let fexp = 0f64;
-                if ss.len() > 2 {
+                if ss.len() != 2 {
                     return Err(format!("Exponent should have size 1, found {:?}", ss));
                 }
-                let exp = if fexp.fract() == 0.0 {
-                    fexp as usize
-                } else {
-                    return Err("Fractional exponent are not supported".to_owned());
+                let exp = match eval(&ss[1])? {
+                    Number::Int(n) => n
+                        .to_u32()
+                        .ok_or_else(|| format!("Exponent {} is not supported", n))?,
+                    _ => return Err("Fractional exponent are not supported".to_owned()),
                 };
-                let base = polynomial(&ss[0])?;
-                let mut result = Polynomial::new("", Number::one());
-                for _ in 0..exp {
-                    result *= &base;
-                }
-                Ok(result)
+                Ok(polynomial(&ss[0])?.pow(exp))
             } else {
-                Ok(Polynomial::new("", eval(s)?))
+                constant(s)
             }
         }
-        S::Unknown(name) => Ok(Polynomial {
-            unknown: name,
-            coefs: vec![Number::zero(), Number::one()],
-        }),
+        S::Unknown(name) => Ok(Polynomial::variable(name)),
+        S::Name(_, s) => polynomial(s),
+        S::Call(_, _, _) | S::List(_) | S::Text(_) | S::Form(_, _) => constant(s),
+    }
+}
+
+// What `s` evaluates to, which can be a polynomial itself, e.g. deriv p
+fn constant(s: &S) -> Parsed<Polynomial> {
+    match eval_value(s)? {
+        Value::Poly(p) => Ok(p),
+        value => Ok(Polynomial::constant(value.into_number()?)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::env::Env;
+    use crate::parser;
+    use crate::pratt;
+
+    fn parse(text: &str) -> Polynomial {
+        let env = Env::prelude();
+        let mut tokens = parser::stmt(text, &env).unwrap().rhs().clone();
+        polynomial(&pratt::expr(&mut tokens, &env).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn _display() {
+        assert_eq!(parse("x^3 - 1").to_string(), "x³ - 1");
+        assert_eq!(parse("(x + y)^2").to_string(), "x² + 2x·y + y²");
+        assert_eq!(parse("1 - x/2 - 3x^2").to_string(), "-3x² - 1/2·x + 1");
+        assert_eq!(parse("x - x").to_string(), "0");
+        // It reads back the same
+        for text in ["x² + 2x·y + y²", "-3x² - 1/2·x + 1", "x¹⁰ - 2"] {
+            assert_eq!(parse(text).to_string(), text);
+        }
+    }
+
+    #[test]
+    fn _div_rem() {
+        let (q, r) = parse("x^3 - 1").div_rem(&parse("x - 2")).unwrap();
+        assert_eq!(
+            (q.to_string(), r.to_string()),
+            ("x² + 2x + 4".into(), "7".into())
+        );
+        let (q, r) = parse("x^2 + 1").div_rem(&parse("2x^3")).unwrap();
+        assert_eq!((q, r), (Polynomial::default(), parse("x^2 + 1")));
+        assert!(parse("x y").div_rem(&parse("x")).is_err());
+        assert!(parse("x").div_rem(&Polynomial::default()).is_err());
+    }
+
+    #[test]
+    fn _gcd() {
+        let gcd = parse("2x^2 - 2").gcd(&parse("x^2 + 2x + 1")).unwrap();
+        assert_eq!(gcd.to_string(), "x + 1");
+        let gcd = parse("x^2 + 1").gcd(&parse("x - 1")).unwrap();
+        assert_eq!(gcd.to_string(), "1");
+    }
+
+    #[test]
+    fn _substitute() {
+        let p = parse("x^2 y - y");
+        let at = p.substitute(&[
+            Polynomial::constant(Number::from(2)),
+            Polynomial::variable("z"),
+        ]);
+        assert_eq!(at.unwrap().to_string(), "3z");
+        assert!(p
+            .substitute(&[Polynomial::constant(Number::from(2))])
+            .is_err());
+        assert_eq!(p.derive("x").to_string(), "2x·y");
+        assert_eq!(p.degree(), Some(3));
     }
 }
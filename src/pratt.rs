@@ -1,19 +1,33 @@
 use crate::env;
-use crate::function::Function;
-use crate::function::MUL;
+use crate::function::{Assoc, Function, F, IMPLIED_MUL};
 
+use crate::parser;
 use crate::parser::Parsed;
+use crate::polynomial::{polynomial, Polynomial};
 use crate::prioritize::Priority;
-use crate::s::S;
+use crate::s::{self, Form, S};
+use crate::series;
+use crate::time;
 use crate::token::Token;
 use crate::token::TokenType;
+use crate::value::Value;
 
 // Modified from https://github.com/matklad/minipratt
 
-pub fn expr<'a>(lexer: &mut Vec<Token<'a>>, env: &'a env::Env) -> Parsed<S<'a>> {
+pub fn expr<'a>(lexer: &mut Vec<Token>, env: &env::Env<'a>) -> Parsed<S<'a>> {
     lexer.reverse();
     pop_if_space(lexer);
-    expr_bp(lexer, env, Priority::MIN)
+    let result = expr_bp(lexer, env, Priority::MIN)?;
+    // Unmatched closing parentheses are ignored, but nothing else that is
+    // left unread, e.g. the < in 2 < 3
+    let unread = lexer
+        .iter()
+        .rev()
+        .find(|it| !matches!(it.ttype, TokenType::Space | TokenType::RParen));
+    match unread {
+        Some(t) => Err(unexpected(t)),
+        None => Ok(result),
+    }
 }
 
 fn pop_if_space<'a>(lexer: &mut Vec<Token<'a>>) -> Option<Token<'a>> {
@@ -24,9 +38,14 @@ fn pop_if_space<'a>(lexer: &mut Vec<Token<'a>>) -> Option<Token<'a>> {
     }
 }
 
-fn pop_spaced_infix(lexer: &mut Vec<Token>) {
+// Pops `op` with the spaces around it, or only the spaces before the next
+// expression when `op` is an implied `*`
+fn pop_spaced_infix(lexer: &mut Vec<Token>, op: &str) {
     pop_if_space(lexer);
-    if let Some(TokenType::Symbol) = lexer.last().map(|it| it.ttype.clone()) {
+    if lexer
+        .last()
+        .is_some_and(|it| it.lexeme == op && it.ttype != TokenType::Space)
+    {
         lexer.pop();
         pop_if_space(lexer);
     }
@@ -54,17 +73,36 @@ fn spaced_infix<'a>(lexer: &mut Vec<Token<'a>>) -> (u16, Option<Token<'a>>) {
     (std::cmp::max(pre_spaces, post_spaces), maybe_token)
 }
 
-fn get_infix_by_name<'f>(name: &str, env: &'f env::Env) -> Function<'f> {
+// Whether the name at the end of `lexer`, after spaces, has parentheses
+// right after it, e.g. gcd(4, 6)
+fn is_called(lexer: &[Token]) -> bool {
+    let mut rest = lexer
+        .iter()
+        .rev()
+        .skip_while(|it| it.ttype == TokenType::Space)
+        .skip(1);
+    rest.next().is_some_and(|it| it.ttype == TokenType::LParen)
+}
+
+// The width of the space at the end of `lexer`, which is read backwards
+fn spaces_before(lexer: &[Token]) -> u16 {
+    match lexer.last() {
+        Some(t) if t.ttype == TokenType::Space => t.lexeme.len() as u16,
+        _ => 0,
+    }
+}
+
+fn get_infix_by_name<'f>(name: &str, env: &env::Env<'f>) -> Function<'f> {
     env.find_binary(name)
         .unwrap_or_else(|_| panic!("Binary {} not found", name))
 }
 
-fn get_prefix_by_name<'f>(name: &str, env: &'f env::Env) -> Function<'f> {
+fn get_prefix_by_name<'f>(name: &str, env: &env::Env<'f>) -> Function<'f> {
     env.find_unary(name)
         .unwrap_or_else(|_| panic!("Unary {} not found", name))
 }
 
-fn rhs<'a>(lexer: &mut Vec<Token<'a>>, env: &'a env::Env, right: u16) -> Parsed<S<'a>> {
+fn rhs<'a>(lexer: &mut Vec<Token>, env: &env::Env<'a>, right: u16) -> Parsed<S<'a>> {
     let spaces = pop_if_space(lexer).map_or(0, |it| it.lexeme.len() as u16);
     expr_bp(
         lexer,
@@ -77,13 +115,15 @@ fn rhs<'a>(lexer: &mut Vec<Token<'a>>, env: &'a env::Env, right: u16) -> Parsed<
 }
 
 fn expr_bp<'a>(
-    lexer: &mut Vec<Token<'a>>,
-    env: &'a env::Env,
+    lexer: &mut Vec<Token>,
+    env: &env::Env<'a>,
     min_priority: Priority,
 ) -> Parsed<S<'a>> {
     let mut lhs = match lexer.pop() {
         Some(t) => match t.ttype {
             TokenType::Literal(n) => S::Var(n),
+            TokenType::Value(v) => S::Value(v),
+            TokenType::Text => S::Text(t.text().to_owned()),
             TokenType::Symbol => {
                 if let Some(right) = prefix_op_priority(t.lexeme, env) {
                     let rhs = rhs(lexer, env, right)?;
@@ -98,78 +138,149 @@ fn expr_bp<'a>(
                 let lhs = expr_bp(lexer, env, Priority::MIN)?;
                 // eof is assumed to close every (, such that eg -(5-6 = 1
                 pop_if_space(lexer);
-                assert_eq!(
-                    lexer.pop().map_or(TokenType::RParen, |it| it.ttype),
-                    TokenType::RParen
-                );
-                lhs
+                match lexer.pop() {
+                    None
+                    | Some(Token {
+                        ttype: TokenType::RParen,
+                        ..
+                    }) => lhs,
+                    Some(t) => return Err(unexpected(&t)),
+                }
+            }
+            TokenType::LBracket => {
+                let close = if t.lexeme == "{" { "}" } else { "]" };
+                S::List(arguments(lexer, env, close)?)
             }
-            // TODO: This assumes every function name is of a unary prefix function.
-            //       Functions with more args should be supported.
             TokenType::Identifier => match env.get(t.lexeme) {
-                Some(env::Variable::Function(fs)) => {
-                    match lexer.last().map(|it| it.ttype.clone()) {
-                        None => {
+                // sum(k^2, k, 1, 100) adds k^2 for k from 1 to 100
+                Some(env::Variable::Function(_)) | None
+                    if (t.lexeme == "sum" || t.lexeme == "prod")
+                        && lexer.last().is_some_and(|it| it.ttype == TokenType::LParen) =>
+                {
+                    lexer.pop();
+                    let args = arguments(lexer, env, ")")?;
+                    match (is_series(&args), env.get(t.lexeme)) {
+                        (true, _) => S::Form(Form::from_name(t.lexeme).expect("is a form"), args),
+                        (false, Some(env::Variable::Function(fs))) => call(t.lexeme, fs, args)?,
+                        (false, _) => {
                             return Err(format!(
-                                "function {} was called with 0 arguments, expected 1",
-                                t.lexeme
-                            ))
+                            "{} expects a term, its variable and 2 bounds, e.g. {}(k, k, 1, 10)",
+                            t.lexeme, t.lexeme
+                        ))
                         }
+                    }
+                }
+                Some(env::Variable::Function(fs)) => {
+                    match lexer.last().map(|it| it.ttype.clone()) {
                         // Special case function application using parens for cases
                         // such as cos(0)-1, otherwise interpreted as cos((0)-1)
                         Some(TokenType::LParen) => {
-                            let rhs = match fs.unary.map(|it| it.priority * 2 + 1) {
-                                Some(_) => rhs(lexer, env, 0xff)?,
-                                None => return Err("expected function".to_owned()),
-                            };
-                            S::Fun(get_prefix_by_name(t.lexeme, env), vec![rhs])
+                            lexer.pop();
+                            let args = arguments(lexer, env, ")")?;
+                            call(t.lexeme, fs, args)?
                         }
-                        Some(_) => {
-                            let rhs = match fs.unary.map(|it| it.priority * 2 + 1) {
-                                Some(right) => rhs(lexer, env, right)?,
-                                None => return Err("expected function".to_owned()),
-                            };
-                            S::Fun(get_prefix_by_name(t.lexeme, env), vec![rhs])
+                        _ => {
+                            let f = fs.with_args(1).ok_or_else(|| {
+                                format!(
+                                    "function {} expects its arguments in parentheses",
+                                    t.lexeme
+                                )
+                            })?;
+                            if lexer.is_empty() {
+                                return Err(format!(
+                                    "function {} was called with 0 arguments, expected 1",
+                                    t.lexeme
+                                ));
+                            }
+                            let rhs = rhs(lexer, env, f.priority * 2 + 1)?;
+                            S::Fun(f, vec![rhs])
                         }
                     }
                 }
-                Some(env::Variable::Value(n)) => S::Var(n.clone()),
-                _ => S::Unknown(t.lexeme),
+                Some(env::Variable::Value(Value::Number(n))) => {
+                    S::Name(t.lexeme.to_owned(), Box::new(S::Var(n.clone())))
+                }
+                // p(2) evaluates a polynomial, while p (2) is still 2p
+                Some(env::Variable::Value(Value::Poly(p)))
+                    if lexer.last().is_some_and(|it| it.ttype == TokenType::LParen) =>
+                {
+                    lexer.pop();
+                    let args = arguments(lexer, env, ")")?;
+                    S::Name(
+                        t.lexeme.to_owned(),
+                        Box::new(S::Value(substitute(p, &args, env)?)),
+                    )
+                }
+                Some(env::Variable::Value(v)) => {
+                    S::Name(t.lexeme.to_owned(), Box::new(S::Value(v.clone())))
+                }
+                Some(env::Variable::Lazy(body)) => {
+                    let mut tokens = parser::stmt(body, env)?.rhs().clone();
+                    S::Name(t.lexeme.to_owned(), Box::new(expr(&mut tokens, env)?))
+                }
+                None if t.lexeme == "now" => {
+                    S::Name(t.lexeme.to_owned(), Box::new(S::Value(env.now())))
+                }
+                None if Form::from_name(t.lexeme).is_some() => {
+                    let form = Form::from_name(t.lexeme).expect("is a form");
+                    S::Form(form, form_arguments(form.name(), lexer, env)?)
+                }
+                None => S::Unknown(t.lexeme.to_owned()),
             },
-            _ => {
-                return Err(format!(
-                    "Column {}: unexpected token {:?}",
-                    t.column, t.lexeme
-                ))
-            }
+            _ => return Err(unexpected(&t)),
         },
         None => return Err("Expected expression, found end of line".to_owned()),
     };
 
     loop {
         let (spaces, maybe_token) = spaced_infix(lexer);
-        let (spaces, op) = match maybe_token {
+        let (spaces, op, implied) = match maybe_token {
             None => break,
             Some(t) => {
                 match t.ttype {
-                    TokenType::Symbol | TokenType::RParen => (spaces, t.lexeme),
+                    TokenType::Comma => break,
+                    // x² is x^2, binding as tightly as the x before it, e.g. 3x² + 1
+                    TokenType::Superscript(n) => {
+                        let (left, _) = infix_op_priority("^", env).expect("^ is built in");
+                        if (Priority {
+                            spaces: spaces_before(lexer),
+                            op_priority: left,
+                        }) < min_priority
+                        {
+                            break;
+                        }
+                        pop_if_space(lexer);
+                        lexer.pop();
+                        lhs = S::Fun(get_infix_by_name("^", env), vec![lhs, S::Var(n)]);
+                        continue;
+                    }
                     // If we don't find a binary operator here, it means we have two expressions next to each other.
-                    // Examples: (2+5) cos7; 2pi; 5+9 7
+                    // Examples: (2+5) cos7; 2pi; 5+9 7; 2√2
                     // In these cases we assume that the user intended the expressions to be multiplied:
-                    // Th examples above become: (2+5)*cos7; 2*pi; 5+9 * 7
-                    TokenType::LParen => (0xffff, "*"),
-                    TokenType::Literal(_) => (spaces, "*"),
-                    TokenType::Identifier => match env.get(t.lexeme) {
-                        Some(env::Variable::Function(fs)) => {
-                            let rhs = match fs.unary.map(|it| it.priority * 2 + 1) {
-                                Some(right) => rhs(lexer, env, right)?,
-                                None => return Err("expected function".to_owned()),
-                            };
-                            return Ok(S::Fun(MUL, vec![lhs, rhs]));
-                        }
-                        Some(env::Variable::Value(_)) => (spaces, "*"),
-                        None => (spaces, "*"),
-                    },
+                    // Th examples above become: (2+5)*cos7; 2*pi; 5+9 * 7; 2*√2
+                    TokenType::Symbol
+                        if env.find_binary(t.lexeme).is_err()
+                            && env.find_unary(t.lexeme).is_ok() =>
+                    {
+                        (spaces_before(lexer), "*", true)
+                    }
+                    TokenType::Symbol | TokenType::RParen | TokenType::RBracket => {
+                        (spaces, t.lexeme, false)
+                    }
+                    // Named operators, e.g. 6 xor 3, unless called as in 2 gcd(4, 6)
+                    TokenType::Identifier
+                        if env.find_binary(t.lexeme).is_ok()
+                            && env.find_unary(t.lexeme).is_err()
+                            && !is_called(lexer) =>
+                    {
+                        (spaces, t.lexeme, false)
+                    }
+                    TokenType::LParen | TokenType::LBracket => (0xffff, "*", true),
+                    // Only the space between the two counts, so that 3h 30min in h
+                    // is (3h 30min) in h
+                    TokenType::Literal(_) | TokenType::Value(_) | TokenType::Identifier => {
+                        (spaces_before(lexer), "*", true)
+                    }
                     _ => {
                         return Err(format!(
                             "Column {}: {:?} was not implemented",
@@ -188,16 +299,24 @@ fn expr_bp<'a>(
             if op_priority < min_priority {
                 break;
             }
-            pop_spaced_infix(lexer);
-            let rhs = expr_bp(
-                lexer,
-                env,
-                Priority {
-                    spaces: std::cmp::min(min_priority.spaces, spaces),
-                    op_priority: right,
-                },
-            )?;
-            lhs = S::Fun(get_infix_by_name(op, env), vec![lhs, rhs]);
+            pop_spaced_infix(lexer, op);
+            let after_number = implied && matches!(lhs, S::Var(_));
+            let rhs = match unit(lexer, env, after_number || op == "in") {
+                Some(unit) => unit,
+                None => expr_bp(
+                    lexer,
+                    env,
+                    Priority {
+                        spaces: std::cmp::min(min_priority.spaces, spaces),
+                        op_priority: right,
+                    },
+                )?,
+            };
+            let f = match implied {
+                true => IMPLIED_MUL,
+                false => get_infix_by_name(op, env),
+            };
+            lhs = apply(f, vec![lhs, rhs], env)?;
             continue;
         }
         break;
@@ -205,9 +324,130 @@ fn expr_bp<'a>(
     Ok(lhs)
 }
 
+// Pops a unit of time where one is expected, e.g. after the number in 90 days
+// or after in, which elsewhere is a name like any other, e.g. h in w*h. Units
+// which are also functions are called with brackets, e.g. min(xs) or min{3, 1}
+fn unit<'a>(lexer: &mut Vec<Token>, env: &env::Env, expected: bool) -> Option<S<'a>> {
+    let t = lexer
+        .last()
+        .filter(|it| it.ttype == TokenType::Identifier)?;
+    let unit = time::unit(t.lexeme).filter(|_| expected)?;
+    let named = matches!(env.get(t.lexeme), Some(env::Variable::Function(_)) | None);
+    let called = lexer
+        .iter()
+        .nth_back(1)
+        .is_some_and(|it| matches!(it.ttype, TokenType::LParen | TokenType::LBracket));
+    if !named || called {
+        return None;
+    }
+    let t = lexer.pop()?;
+    Some(S::Name(t.lexeme.to_owned(), Box::new(S::Value(unit))))
+}
+
+// The arguments of a function called with parentheses, which can't be
+// called without, e.g. load("data.csv")
+fn form_arguments<'a>(
+    name: &str,
+    lexer: &mut Vec<Token>,
+    env: &env::Env<'a>,
+) -> Parsed<Vec<S<'a>>> {
+    pop_if_space(lexer);
+    if lexer.pop().is_none_or(|it| it.ttype != TokenType::LParen) {
+        return Err(format!("{} expects its arguments in parentheses", name));
+    }
+    arguments(lexer, env, ")")
+}
+
+// The value of `p` for its variables in alphabetical order, which can also be
+// expressions of unknowns, e.g. p(x + 1)
+fn substitute(p: &Polynomial, args: &[S], env: &env::Env) -> Parsed<Value> {
+    let values = args
+        .iter()
+        .map(|it| match s::eval_env(it, env) {
+            Ok((Value::Poly(q), _)) => Ok(q),
+            Ok((value, _)) => Ok(Polynomial::constant(value.into_number()?)),
+            Err(e) => polynomial(it).map_err(|_| e),
+        })
+        .collect::<Parsed<Vec<_>>>()?;
+    Ok(Value::from(p.substitute(&values)?))
+}
+
+// Whether the arguments of a sum or product are a term, its variable and 2
+// bounds, rather than numbers, e.g. sum(x, y)
+fn is_series(args: &[S]) -> bool {
+    match args {
+        [_, S::Unknown(_), _, _] => true,
+        [term, S::Name(k, _), _, _] => series::degree(term, k) != Some(0),
+        _ => false,
+    }
+}
+
+fn unexpected(t: &Token) -> String {
+    format!("Column {}: unexpected token {:?}", t.column, t.lexeme)
+}
+
+// The arguments of a call such as `f(x, y)`, or the elements of a list such
+// as `{1, 2}`, after the opening bracket and up to `close`
+fn arguments<'a>(lexer: &mut Vec<Token>, env: &env::Env<'a>, close: &str) -> Parsed<Vec<S<'a>>> {
+    pop_if_space(lexer);
+    let mut args = vec![];
+    if lexer.last().is_some_and(|it| it.lexeme == close) {
+        lexer.pop();
+        return Ok(args);
+    }
+    loop {
+        pop_if_space(lexer);
+        args.push(expr_bp(lexer, env, Priority::MIN)?);
+        pop_if_space(lexer);
+        match lexer.pop() {
+            Some(Token {
+                ttype: TokenType::Comma,
+                ..
+            }) => continue,
+            // eof is assumed to close every bracket
+            None => return Ok(args),
+            Some(t) if t.lexeme == close => return Ok(args),
+            Some(t) => return Err(unexpected(&t)),
+        }
+    }
+}
+
+fn call<'a>(name: &str, fs: &env::Functions<'a>, args: Vec<S<'a>>) -> Parsed<S<'a>> {
+    match fs.with_args(args.len()) {
+        Some(f) => Ok(S::Fun(f, args)),
+        None => Err(format!(
+            "function {} can't be called with {} arguments",
+            name,
+            args.len()
+        )),
+    }
+}
+
+// The application of `f`, with the body of operators defined in cwim parsed
+// like the definitions of variables are.
+fn apply<'a>(f: Function<'a>, args: Vec<S<'a>>, env: &env::Env<'a>) -> Parsed<S<'a>> {
+    match env.definition(&f.name) {
+        Some(definition) if matches!(f.f, F::Defined(_)) => {
+            let mut tokens = parser::stmt(&definition.body, env)?.rhs().clone();
+            let body = expr(&mut tokens, env)?;
+            Ok(S::Call(f, args, Box::new(body)))
+        }
+        _ => Ok(S::Fun(f, args)),
+    }
+}
+
 fn infix_op_priority(op: &str, env: &env::Env) -> Option<(u16, u16)> {
     match env.find_binary(op) {
-        Ok(Function { priority, .. }) => Some((priority * 2, priority * 2 + 1)),
+        Ok(Function {
+            priority,
+            assoc: Assoc::Left,
+            ..
+        }) => Some((priority * 2, priority * 2 + 1)),
+        Ok(Function {
+            priority,
+            assoc: Assoc::Right,
+            ..
+        }) => Some((priority * 2 + 1, priority * 2)),
         _ => None,
     }
 }
@@ -222,7 +462,7 @@ fn prefix_op_priority(op: &str, env: &env::Env) -> Option<u16> {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::parser::{self, Stmt};
+    use crate::parser::Stmt;
 
     fn tokenize_and_parse(input: &str, expected: &str) {
         let stmt = parser::stmt(input, &env::Env::prelude()).unwrap();
@@ -321,8 +561,44 @@ mod test {
         tokenize_and_parse("2pi", "(* 2 3.141592653589793)")
     }
 
+    #[test]
+    fn _lazy() {
+        let mut env = env::Env::prelude();
+        env.define("area", "w*h").unwrap();
+        env.assign("w".to_owned(), crate::number::Number::from(3));
+        let stmt = parser::stmt("2area", &env).unwrap();
+        match stmt {
+            Stmt::Expr(mut tokens) => {
+                let actual = expr(&mut tokens, &env).unwrap();
+                assert_eq!(actual.to_string(), "(* 2 (* 3 h))");
+            }
+            _ => panic!("expected expression"),
+        }
+    }
+
     #[test]
     fn _implied_multiplication_and_fn_apply() {
         tokenize_and_parse("cos(1)-2", "(- (cos 1) 2)");
     }
+
+    #[test]
+    fn _right_assoc() {
+        tokenize_and_parse("2^3^2", "(^ 2 (^ 3 2))");
+        tokenize_and_parse("2^3^2^4", "(^ 2 (^ 3 (^ 2 4)))");
+        tokenize_and_parse("2-3-2", "(- (- 2 3) 2)");
+        tokenize_and_parse("-2^2", "(- (^ 2 2))");
+        tokenize_and_parse("2^-3^2", "(^ 2 (- (^ 3 2)))");
+        tokenize_and_parse("2*3^2^2*4", "(* (* 2 (^ 3 (^ 2 2))) 4)");
+    }
+
+    #[test]
+    fn _right_assoc_and_spaces() {
+        // Spaces still group first what has fewer spaces around it
+        tokenize_and_parse("2^3 ^2", "(^ (^ 2 3) 2)");
+        tokenize_and_parse("2 ^3^2", "(^ 2 (^ 3 2))");
+        tokenize_and_parse("2 ^ 3 ^ 2", "(^ 2 (^ 3 2))");
+        tokenize_and_parse("2 ^ 3^2 ^ 2", "(^ 2 (^ (^ 3 2) 2))");
+        tokenize_and_parse("2^3  ^ 2^2", "(^ (^ 2 3) (^ 2 2))");
+        tokenize_and_parse("2 ^ 3+1", "(^ 2 (+ 3 1))");
+    }
 }
@@ -1,58 +1,656 @@
 use crate::{
-    function::{Function, F},
+    function::{Category, Function, F, SHL},
     parser::Parsed,
 };
+use num::Signed;
+use num::ToPrimitive;
+use std::cell::Cell;
 use std::fmt;
+use std::path::Path;
 
+use crate::calculus;
+use crate::currency::{self, Rates};
+use crate::data::{self, Column};
+use crate::env::Env;
 use crate::number::Number;
+use crate::polynomial::{polynomial, Polynomial};
+use crate::series;
+use crate::value::Value;
+use crate::word::Word;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum S<'a> {
     Var(Number),
+    // A date or duration, e.g. from 2026-10-17 or now
+    Value(Value),
     Fun(Function<'a>, Vec<S<'a>>),
-    Unknown(&'a str),
+    // {1, 2, 3}
+    List(Vec<S<'a>>),
+    Unknown(String),
+    // A variable or definition, with what it stood for when parsed
+    Name(String, Box<S<'a>>),
+    // An operator defined in cwim, with its arguments and body
+    Call(Function<'a>, Vec<S<'a>>, Box<S<'a>>),
+    // Text in quotes, e.g. the file in load("data.csv")
+    Text(String),
+    // A function of its arguments as they were typed, e.g. load("data.csv")
+    Form(Form, Vec<S<'a>>),
+}
+
+// Functions which take text or expressions rather than values, and are
+// still only evaluated with the rest, e.g. so that files aren't read while typing
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Form {
+    // load("measurements.csv", "temp") reads a column of a file as a list,
+    // the column being a name, a position or left out
+    Load,
+    // fit(xs, ys, 2) is the least-squares polynomial of degree 2 in x, and
+    // r2(xs, ys, 2) how well it fits
+    Fit,
+    R2,
+    // sum(k^2, k, 1, 100) adds k^2 for k from 1 to 100, and prod multiplies
+    Sum,
+    Prod,
+    // limit(sin(x)/x, x, 0) is the limit when x goes to 0, and taylor(exp x, x, 0, 5)
+    // the Taylor series at 0 up to x^5, as a polynomial in x
+    Limit,
+    Taylor,
+}
+
+// Beyond these, exact fits take too long
+const MAX_FIT_DEGREE: usize = 10;
+const MAX_FIT_POINTS: usize = 10_000;
+const MAX_ORDER: usize = 20;
+
+impl Form {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "load" => Some(Form::Load),
+            "fit" => Some(Form::Fit),
+            "r2" => Some(Form::R2),
+            "sum" => Some(Form::Sum),
+            "prod" => Some(Form::Prod),
+            "limit" => Some(Form::Limit),
+            "taylor" => Some(Form::Taylor),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Form::Load => "load",
+            Form::Fit => "fit",
+            Form::R2 => "r2",
+            Form::Sum => "sum",
+            Form::Prod => "prod",
+            Form::Limit => "limit",
+            Form::Taylor => "taylor",
+        }
+    }
 }
 
 impl<'a> fmt::Display for S<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             S::Var(i) => write!(f, "{}", i),
-            S::Fun(head, rest) => {
+            S::Value(v) => write!(f, "{}", v),
+            S::Fun(head, rest) | S::Call(head, rest, _) => {
                 write!(f, "({}", head.name)?;
                 for s in rest {
                     write!(f, " {}", s)?
                 }
                 write!(f, ")")
             }
+            S::List(items) => {
+                write!(f, "{{")?;
+                for (i, s) in items.iter().enumerate() {
+                    write!(f, "{}{}", if i > 0 { " " } else { "" }, s)?
+                }
+                write!(f, "}}")
+            }
             S::Unknown(x) => write!(f, "{}", x),
+            S::Name(_, s) => write!(f, "{}", s),
+            S::Text(text) => write!(f, "{:?}", text),
+            S::Form(form, args) => {
+                write!(f, "({}", form.name())?;
+                for s in args {
+                    write!(f, " {}", s)?
+                }
+                write!(f, ")")
+            }
         }
     }
 }
 
-pub fn eval(s: &S) -> Parsed<Number> {
-    match s {
-        S::Var(n) => Ok(n.clone()),
-        S::Fun(fun, ss) => match fun.f {
-            F::Nary(f) => Ok(f(eval(&ss[0])?)),
-            F::Binary(f) => {
-                let mut result = None;
-                for s in ss {
-                    let next = eval(s)?;
-                    result = match result {
-                        None => Some(next),
-                        Some(curr) => Some(f(next, curr)),
+// Shows an expression the way it would be typed, with parentheses around every
+// operation that's part of another one, e.g. 2^(x+y)
+pub struct Infix<'s, 'a>(pub &'s S<'a>);
+
+impl<'s, 'a> Infix<'s, 'a> {
+    fn is_atomic(&self) -> bool {
+        match self.0 {
+            S::Var(Number::Int(n)) => !n.is_negative(),
+            S::Var(Number::Flt(n)) => !n.is_sign_negative(),
+            S::Var(Number::Rat(_)) => false,
+            S::Value(v) => matches!(v, Value::Date(_)),
+            S::Fun(head, _) => is_named(head),
+            S::Call(_, _, _) => false,
+            S::List(_) | S::Unknown(_) | S::Name(_, _) | S::Text(_) | S::Form(_, _) => true,
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_atomic() {
+            write!(f, "{}", self)
+        } else {
+            write!(f, "({})", self)
+        }
+    }
+}
+
+fn is_named(function: &Function) -> bool {
+    function.name.starts_with(|c: char| c.is_alphabetic())
+}
+
+fn fmt_call(f: &mut fmt::Formatter<'_>, name: &str, args: &[S]) -> fmt::Result {
+    write!(f, "{}(", name)?;
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", Infix(arg))?;
+    }
+    write!(f, ")")
+}
+
+impl<'s, 'a> fmt::Display for Infix<'s, 'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            S::Var(n) => write!(f, "{}", n),
+            S::Value(v) => write!(f, "{}", v),
+            S::Unknown(x) | S::Name(x, _) => write!(f, "{}", x),
+            S::Text(text) => write!(f, "{:?}", text),
+            S::List(items) => {
+                write!(f, "{{")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
                     }
+                    write!(f, "{}", Infix(item))?;
                 }
-                match result {
-                    None => Err(format!(
-                        "Binary function {} was called with no arguments",
-                        fun.name
-                    )),
-                    Some(n) => Ok(n),
+                write!(f, "}}")
+            }
+            S::Fun(head, args) if is_named(head) => fmt_call(f, &head.name, args),
+            S::Form(form, args) => fmt_call(f, form.name(), args),
+            S::Fun(head, args) if args.len() == 1 => {
+                write!(f, "{}", head.name)?;
+                Infix(&args[0]).fmt_operand(f)
+            }
+            S::Fun(head, args) | S::Call(head, args, _) => {
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{}", head.name)?;
+                    }
+                    Infix(arg).fmt_operand(f)?;
                 }
+                Ok(())
             }
+        }
+    }
+}
+
+pub fn eval(s: &S) -> Parsed<Number> {
+    eval_value(s)?.into_number()
+}
+
+// Evaluates to a number, date, duration or amount of money
+pub fn eval_value(s: &S) -> Parsed<Value> {
+    eval_in(s, &|_| None, &Context::new(None, &NO_RATES))
+}
+
+// Evaluates with the values `bindings` gives to names, instead of what they
+// stood for when parsed.
+pub fn eval_with(s: &S, bindings: &dyn Fn(&str) -> Option<Number>) -> Parsed<Number> {
+    let cx = Context::new(None, &NO_RATES);
+    eval_in(s, &|name| bindings(name).map(Value::Number), &cx)?.into_number()
+}
+
+// Evaluates with the exchange rates of `env`, and with integers wrapped to
+// the word of its settings after every operation, if any. Says whether one of
+// them overflowed.
+pub fn eval_env(s: &S, env: &Env) -> Parsed<(Value, bool)> {
+    eval_word(s, &|_| None, env.settings.word, &env.rates)
+}
+
+// Evaluates with `bindings` for some of the names, and otherwise like
+// eval_env, with a word and rates of its own
+pub fn eval_word(
+    s: &S,
+    bindings: &dyn Fn(&str) -> Option<Number>,
+    word: Option<Word>,
+    rates: &Rates,
+) -> Parsed<(Value, bool)> {
+    let cx = Context::new(word, rates);
+    let value = eval_in(s, &|name| bindings(name).map(Value::Number), &cx)?;
+    Ok((value, cx.overflow.get()))
+}
+
+static NO_RATES: Rates = Rates::EMPTY;
+
+// What evaluating needs besides bindings
+struct Context<'r> {
+    word: Option<Word>,
+    overflow: Cell<bool>,
+    rates: &'r Rates,
+}
+
+impl<'r> Context<'r> {
+    fn new(word: Option<Word>, rates: &'r Rates) -> Self {
+        Self {
+            word,
+            overflow: Cell::new(false),
+            rates,
+        }
+    }
+
+    // Values typed in are wrapped silently, e.g. 0xffffffff is -1 in 32 bits
+    fn value(&self, value: Value) -> Value {
+        match (self.word, value) {
+            (Some(word), Value::Number(n)) => Value::Number(word.wrap(n).0),
+            (_, value) => value,
+        }
+    }
+
+    // Bit operations wrap without overflowing, e.g. ~0 in unsigned words,
+    // except for shifting bits out on the left
+    fn result(&self, f: &Function, n: Number) -> Number {
+        match self.word {
+            Some(word) => {
+                let (n, overflow) = word.wrap(n);
+                let bitwise = f.doc.category == Category::Bitwise && *f != SHL;
+                self.overflow
+                    .set(self.overflow.get() || overflow && !bitwise);
+                n
+            }
+            None => n,
+        }
+    }
+}
+
+// The names bound around an expression, with its context, to evaluate it
+// again for values of one more name, e.g. the terms of a sum
+pub struct Scope<'s> {
+    bindings: &'s dyn Fn(&str) -> Option<Value>,
+    cx: &'s Context<'s>,
+}
+
+impl<'s> Scope<'s> {
+    // Runs `f` with nothing bound, no word and no rates
+    pub fn empty<T>(f: impl FnOnce(&Scope) -> T) -> T {
+        let cx = Context::new(None, &NO_RATES);
+        f(&Scope {
+            bindings: &|_| None,
+            cx: &cx,
+        })
+    }
+
+    // The value of `s` with x = n, other names being what they are around it
+    pub fn at(&self, s: &S, x: &str, n: Number) -> Parsed<Number> {
+        let n = Value::Number(n);
+        let bindings = |name: &str| match name == x {
+            true => Some(n.clone()),
+            false => (self.bindings)(name),
+        };
+        eval_in(s, &bindings, self.cx)?.into_number()
+    }
+
+    // `n` wrapped to the word, if any, as the result of `f`
+    pub fn result(&self, f: &Function, n: Number) -> Number {
+        self.cx.result(f, n)
+    }
+}
+
+fn eval_in(s: &S, bindings: &dyn Fn(&str) -> Option<Value>, cx: &Context) -> Parsed<Value> {
+    match s {
+        S::Var(n) => Ok(cx.value(Value::Number(n.clone()))),
+        S::Value(v) => Ok(v.clone()),
+        S::Fun(fun, ss) => {
+            let args: Vec<_> = ss.iter().map(|s| eval_in(s, bindings, cx)).collect();
+            // Unknowns are variables next to polynomials, e.g. in p / (x - 1), and
+            // in the arguments of functions of polynomials, e.g. deriv(x^3)
+            let symbolic = matches!(fun.f, F::Polynomial(_))
+                || args.iter().any(|it| matches!(it, Ok(Value::Poly(_))));
+            let args = ss
+                .iter()
+                .zip(args)
+                .map(|(s, arg)| match symbolic && has_unknowns(s, bindings) {
+                    true => polynomial(s).map(Value::from).or(arg),
+                    false => arg,
+                })
+                .collect::<Parsed<Vec<_>>>()?;
+            if matches!(fun.f, F::Stat(_) | F::StatWith(_)) {
+                return apply(fun, spread(args)?, cx).map(Value::Number);
+            }
+            if args.iter().any(|it| matches!(it, Value::List(_))) {
+                return broadcast(fun, args, cx);
+            }
+            if let F::Polynomial(f) = &fun.f {
+                return f(&args);
+            }
+            // Dates and durations only go through the arithmetic defined on them
+            if !args.iter().all(|it| matches!(it, Value::Number(_))) {
+                return Value::apply(fun, args, cx.rates);
+            }
+            let args = args
+                .into_iter()
+                .map(Value::into_number)
+                .collect::<Parsed<Vec<_>>>()?;
+            apply(fun, args, cx).map(Value::Number)
+        }
+        S::List(items) => {
+            let items = items
+                .iter()
+                .map(|s| eval_in(s, bindings, cx)?.into_number())
+                .collect::<Parsed<Vec<_>>>()?;
+            Ok(Value::List(items))
+        }
+        S::Unknown(x) => match bindings(x) {
+            Some(value) => Ok(cx.value(value)),
+            None => currency::unit(x, cx.rates)
+                .ok_or_else(|| format!("tried to evaluate unknown {}", x)),
+        },
+        S::Name(x, s) => {
+            bindings(x).map_or_else(|| eval_in(s, bindings, cx), |it| Ok(cx.value(it)))
+        }
+        S::Text(text) => Err(format!("Expected a number, found the text {:?}", text)),
+        S::Form(Form::Load, args) => load(args, bindings, cx),
+        S::Form(form @ (Form::Fit | Form::R2), args) => fit(*form, args, bindings, cx),
+        S::Form(form @ (Form::Sum | Form::Prod), args) => series(*form, args, bindings, cx),
+        S::Form(form @ (Form::Limit | Form::Taylor), args) => calculus(*form, args, bindings, cx),
+        S::Call(fun, args, body) => {
+            let F::Defined(definition) = &fun.f else {
+                return Err(format!("{} has no definition", fun.name));
+            };
+            let args = args
+                .iter()
+                .map(|s| eval_in(s, bindings, cx))
+                .collect::<Parsed<Vec<_>>>()?;
+            let params = |name: &str| {
+                let i = definition.params.iter().position(|it| it == name)?;
+                args.get(i).cloned()
+            };
+            eval_in(body, &|name| params(name).or_else(|| bindings(name)), cx)
+        }
+    }
+}
+
+fn load(args: &[S], bindings: &dyn Fn(&str) -> Option<Value>, cx: &Context) -> Parsed<Value> {
+    let (path, column) = match args {
+        [S::Text(path)] => (path, Column::Only),
+        [S::Text(path), S::Text(name)] => (path, Column::Name(name.clone())),
+        [S::Text(path), position] => {
+            let position = match eval_in(position, bindings, cx)?.into_number()? {
+                Number::Int(n) => n.to_usize(),
+                _ => None,
+            };
+            let position = position.ok_or("Expected a column number")?;
+            (path, Column::Position(position))
+        }
+        [s] | [s, _] => return Err(format!("Expected a file name in quotes, found {}", s)),
+        _ => {
+            return Err(format!(
+                "load was called with {} arguments, expected 1 or 2",
+                args.len()
+            ))
+        }
+    };
+    data::load(Path::new(path), &column).map(Value::List)
+}
+
+fn fit(
+    form: Form,
+    args: &[S],
+    bindings: &dyn Fn(&str) -> Option<Value>,
+    cx: &Context,
+) -> Parsed<Value> {
+    let name = form.name();
+    let values = args
+        .iter()
+        .map(|it| eval_in(it, bindings, cx))
+        .collect::<Parsed<Vec<_>>>()?;
+    let (xs, ys, degree) = match &values[..] {
+        [Value::List(xs), Value::List(ys), Value::Number(Number::Int(n))] => (xs, ys, n),
+        [_, _, _] => {
+            return Err(format!(
+                "{} expects 2 lists and a degree, e.g. {}(xs, ys, 1)",
+                name, name
+            ))
+        }
+        _ => {
+            return Err(format!(
+                "function {} was called with {} arguments, expected 3",
+                name,
+                values.len()
+            ))
+        }
+    };
+    if xs.len() > MAX_FIT_POINTS {
+        return Err(format!(
+            "Can't fit more than {} points, found {}",
+            MAX_FIT_POINTS,
+            xs.len()
+        ));
+    }
+    let degree = degree
+        .to_usize()
+        .filter(|it| *it < xs.len().max(1) && *it <= MAX_FIT_DEGREE)
+        .ok_or_else(|| {
+            format!(
+                "Can't fit a polynomial of degree {} to {} points, the degree being at most {}",
+                degree,
+                xs.len(),
+                MAX_FIT_DEGREE
+            )
+        })?;
+    let p = Polynomial::fit("x", xs, ys, degree)?;
+    Ok(match form {
+        Form::R2 => Value::Number(p.r2(xs, ys)),
+        _ => Value::from(p),
+    })
+}
+
+// Sums of polynomials up to an unknown n are polynomials in n
+fn series(
+    form: Form,
+    args: &[S],
+    bindings: &dyn Fn(&str) -> Option<Value>,
+    cx: &Context,
+) -> Parsed<Value> {
+    let [term, S::Unknown(k) | S::Name(k, _), from, to] = args else {
+        return Err(format!(
+            "{} expects a term, its variable and 2 bounds, e.g. {}(k, k, 1, 10)",
+            form.name(),
+            form.name()
+        ));
+    };
+    let number = |s: &S| eval_in(s, bindings, cx)?.into_number();
+    let scope = Scope { bindings, cx };
+    let from = number(from)?;
+    if let (S::Unknown(n), Form::Sum, Some(degree), Number::Int(start)) =
+        (to, form, series::degree(term, k), &from)
+    {
+        if bindings(n).is_none() {
+            let p = series::closed_form(term, k, start, n, degree, &scope)?;
+            return Ok(Value::from(p));
+        }
+    }
+    let to = number(to)?;
+    match form {
+        Form::Prod => series::prod(term, k, &from, &to, &scope),
+        _ => series::sum(term, k, &from, &to, &scope),
+    }
+    .map(Value::Number)
+}
+
+fn calculus(
+    form: Form,
+    args: &[S],
+    bindings: &dyn Fn(&str) -> Option<Value>,
+    cx: &Context,
+) -> Parsed<Value> {
+    let number = |s: &S| eval_in(s, bindings, cx)?.into_number();
+    let scope = Scope { bindings, cx };
+    match (form, args) {
+        (Form::Limit, [f, S::Unknown(x) | S::Name(x, _), a]) => {
+            calculus::limit(f, x, &number(a)?, &scope).map(Value::Number)
+        }
+        (Form::Taylor, [f, S::Unknown(x) | S::Name(x, _), a, n]) => {
+            let n = match number(n)? {
+                Number::Int(n) => n.to_usize().filter(|it| *it <= MAX_ORDER),
+                _ => None,
+            }
+            .ok_or_else(|| format!("Expected an order from 0 to {}", MAX_ORDER))?;
+            calculus::taylor(f, x, &number(a)?, n, &scope).map(Value::from)
+        }
+        (Form::Limit, _) => Err("limit expects an expression, its variable and where it goes, e.g. limit(sin(x)/x, x, 0)".to_owned()),
+        _ => Err("taylor expects an expression, its variable, where and the order, e.g. taylor(exp x, x, 0, 5)".to_owned()),
+    }
+}
+
+// Whether `s` has names without a value, which are variables next to polynomials
+fn has_unknowns(s: &S, bindings: &dyn Fn(&str) -> Option<Value>) -> bool {
+    match s {
+        S::Var(_) | S::Value(_) | S::Text(_) | S::Form(_, _) => false,
+        S::Unknown(x) => bindings(x).is_none(),
+        S::Name(x, s) => bindings(x).is_none() && has_unknowns(s, bindings),
+        S::Fun(_, args) | S::List(args) | S::Call(_, args, _) => {
+            args.iter().any(|it| has_unknowns(it, bindings))
+        }
+    }
+}
+
+// Roughly how much work evaluating `s` takes, counted in operations
+pub fn cost(s: &S) -> usize {
+    match s {
+        S::Var(_) | S::Unknown(_) | S::Text(_) => 1,
+        S::Value(Value::List(items)) => items.len().max(1),
+        S::Value(_) => 1,
+        S::Name(_, s) => cost(s),
+        S::Fun(_, args) | S::List(args) => total_cost(args),
+        S::Call(_, args, body) => total_cost(args).saturating_add(cost(body)),
+        // Files may be large, or never end, e.g. pipes
+        S::Form(Form::Load, _) => usize::MAX,
+        // Powers of every point are added up to twice the degree
+        S::Form(Form::Fit | Form::R2, args) => {
+            total_cost(args).saturating_mul(2 * MAX_FIT_DEGREE + 1)
+        }
+        S::Form(Form::Sum | Form::Prod, args) => match &args[..] {
+            [_, _, from, to] => series::count(from, to).saturating_mul(total_cost(args)),
+            _ => total_cost(args),
         },
-        S::Unknown(x) => Err(format!("tried to evaluate unknown {}", x)),
+        // Quotients are differentiated up to as many times
+        S::Form(Form::Limit, args) => total_cost(args).saturating_mul(1 << calculus::MAX_DEPTH),
+        // Each coefficient of each operation adds up as many products
+        S::Form(Form::Taylor, args) => {
+            let order = match args.get(3) {
+                Some(S::Var(Number::Int(n))) => n.to_usize().unwrap_or(MAX_ORDER),
+                _ => MAX_ORDER,
+            };
+            let order = order.min(MAX_ORDER) + 1;
+            total_cost(args).saturating_mul(order * order)
+        }
+    }
+}
+
+fn total_cost(args: &[S]) -> usize {
+    args.iter().map(cost).fold(1, usize::saturating_add)
+}
+
+// The numbers of `args`, with those of lists in their place, e.g. for sum(xs, 4)
+fn spread(args: Vec<Value>) -> Parsed<Vec<Number>> {
+    let mut numbers = vec![];
+    for arg in args {
+        match arg {
+            Value::List(items) => numbers.extend(items),
+            arg => numbers.push(arg.into_number()?),
+        }
+    }
+    Ok(numbers)
+}
+
+// Applies `fun` to every element of the lists in `args`, which must have the
+// same length, with the numbers next to them, e.g. sqrt{4, 9} or {1, 2} * 10
+fn broadcast(fun: &Function, args: Vec<Value>, cx: &Context) -> Parsed<Value> {
+    let mut len = None;
+    for arg in &args {
+        if let Value::List(items) = arg {
+            match len {
+                Some(n) if n != items.len() => {
+                    return Err(format!(
+                        "Can't apply {} to lists of {} and {} values",
+                        fun.name,
+                        n,
+                        items.len()
+                    ))
+                }
+                _ => len = Some(items.len()),
+            }
+        }
+    }
+    let mut result = vec![];
+    for i in 0..len.unwrap_or(0) {
+        let args = args
+            .iter()
+            .map(|arg| match arg {
+                Value::List(items) => Ok(items[i].clone()),
+                arg => arg.clone().into_number(),
+            })
+            .collect::<Parsed<Vec<_>>>()?;
+        result.push(apply(fun, args, cx)?);
+    }
+    Ok(Value::List(result))
+}
+
+fn apply(fun: &Function, args: Vec<Number>, cx: &Context) -> Parsed<Number> {
+    let no_arguments = || format!("{} was called with no arguments", fun.name);
+    match &fun.f {
+        F::Nary(f) => {
+            let x = args.into_iter().next().ok_or_else(no_arguments)?;
+            Ok(cx.result(fun, f(x)))
+        }
+        F::NativeUnary(f) => {
+            let x = args.into_iter().next().ok_or_else(no_arguments)?;
+            f(x).map(|it| cx.result(fun, it)).map_err(|e| e.to_string())
+        }
+        F::NativeBinary(f) => {
+            let mut args = args.into_iter();
+            let mut result = args.next().ok_or_else(no_arguments)?;
+            for next in args {
+                result = cx.result(fun, f(result, next).map_err(|e| e.to_string())?);
+            }
+            Ok(result)
+        }
+        F::Stat(f) => Ok(f(&args)),
+        F::StatWith(f) => {
+            let mut args = args;
+            let last = args.pop().ok_or_else(no_arguments)?;
+            Ok(f(&args, last))
+        }
+        F::NativeVariadic(f) => f(&args)
+            .map(|it| cx.result(fun, it))
+            .map_err(|e| e.to_string()),
+        F::Defined(_) => Err(format!("{} was parsed without its definition", fun.name)),
+        F::Polynomial(f) => {
+            f(&args.into_iter().map(Value::Number).collect::<Vec<_>>())?.into_number()
+        }
+        F::Binary(f) | F::Implied(f) => {
+            let mut args = args.into_iter();
+            let mut result = args.next().ok_or_else(no_arguments)?;
+            for next in args {
+                result = cx.result(fun, f(next, result));
+            }
+            Ok(result)
+        }
     }
 }
 
@@ -93,4 +691,25 @@ mod test {
             _ => panic!(),
         }
     }
+
+    #[test]
+    fn _infix() {
+        let mut env = env::Env::prelude();
+        env.assign("x".to_owned(), Number::from(3));
+        for (input, expected) in [
+            ("2^ x+y", "2^(x+y)"),
+            ("1+2*3", "1+(2*3)"),
+            ("cos 2pi", "cos(2*pi)"),
+            ("-(5+ -6)-7", "(-(5+(-6)))-7"),
+            ("0.5x", "(1/2)*x"),
+        ] {
+            match parser::stmt(input, &env).unwrap() {
+                Stmt::Expr(mut tokens) => {
+                    let actual = expr(&mut tokens, &env).unwrap();
+                    assert_eq!(Infix(&actual).to_string(), expected);
+                }
+                _ => panic!(),
+            }
+        }
+    }
 }
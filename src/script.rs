@@ -0,0 +1,128 @@
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use crate::parser::{strip_comment, Parsed};
+
+// A statement read from a script, with where it came from for error messages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub text: String,
+    pub origin: String,
+    pub number: usize,
+}
+
+impl Display for Line {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.origin, self.number)
+    }
+}
+
+pub fn load(path: &Path) -> Parsed<Vec<Line>> {
+    let mut lines = vec![];
+    include(path, &mut vec![], &mut lines)?;
+    Ok(lines)
+}
+
+// Splits a script into statements:
+// - `#` starts a comment, which runs until the end of the line
+// - a line ending in `\` continues on the next line, without its indentation
+// - `include "other.cwim"` reads statements from other.cwim, relative to `dir`
+pub fn lines(text: &str, origin: &str, dir: &Path) -> Parsed<Vec<Line>> {
+    let mut lines = vec![];
+    read(text, origin, dir, &mut vec![], &mut lines)?;
+    Ok(lines)
+}
+
+fn include(path: &Path, stack: &mut Vec<PathBuf>, lines: &mut Vec<Line>) -> Parsed<()> {
+    let canonical = path
+        .canonicalize()
+        .map_err(|e| format!("Can't read '{}': {}", path.display(), e))?;
+    if stack.contains(&canonical) {
+        return Err(format!("'{}' includes itself", path.display()));
+    }
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Can't read '{}': {}", path.display(), e))?;
+    let dir = path.parent().unwrap_or(Path::new("."));
+    stack.push(canonical);
+    read(&text, &path.display().to_string(), dir, stack, lines)?;
+    stack.pop();
+    Ok(())
+}
+
+fn read(
+    text: &str,
+    origin: &str,
+    dir: &Path,
+    stack: &mut Vec<PathBuf>,
+    lines: &mut Vec<Line>,
+) -> Parsed<()> {
+    let mut pending: Option<Line> = None;
+    for (i, raw) in text.lines().enumerate() {
+        let code = strip_comment(raw).trim_end();
+        let mut line = match pending.take() {
+            // Spaces change priorities, so the indentation of the continued line doesn't count
+            Some(mut line) => {
+                line.text += code.trim_start();
+                line
+            }
+            None => Line {
+                text: code.to_owned(),
+                origin: origin.to_owned(),
+                number: i + 1,
+            },
+        };
+        if let Some(text) = line.text.strip_suffix('\\') {
+            line.text = text.to_owned();
+            pending = Some(line);
+        } else if let Some(path) = line.text.trim().strip_prefix("include ") {
+            let path = path.trim();
+            match path.strip_prefix('"').and_then(|it| it.strip_suffix('"')) {
+                Some(path) => include(&dir.join(path), stack, lines)
+                    .map_err(|e| format!("{}: {}", line, e))?,
+                None => return Err(format!("{}: expected a quoted path after include", line)),
+            }
+        } else if !line.text.trim().is_empty() {
+            lines.push(line);
+        }
+    }
+    match pending {
+        Some(line) => Err(format!("{}: expected another line after '\\'", line)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn texts(lines: Vec<Line>) -> Vec<String> {
+        lines.into_iter().map(|it| it.text).collect()
+    }
+
+    #[test]
+    fn _comments_and_continuations() {
+        let script = "# totals\nx = 2 # two\n\n1 +\\\n  x\ny";
+        let lines = lines(script, "test", Path::new(".")).unwrap();
+        assert_eq!(lines[1].to_string(), "test:4");
+        assert_eq!(texts(lines), vec!["x = 2", "1 +x", "y"]);
+        let lines = super::lines("x = 2*\\\n    3+1", "test", Path::new(".")).unwrap();
+        assert_eq!(texts(lines), vec!["x = 2*3+1"]);
+        assert!(super::lines("1 +\\", "test", Path::new(".")).is_err());
+    }
+
+    #[test]
+    fn _include() {
+        // A directory of its own, so that parallel or leftover runs don't collide
+        let dir = std::env::temp_dir().join(format!("cwim_include_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.cwim"), "x = 1\ninclude \"b.cwim\"\nx + y").unwrap();
+        std::fs::write(dir.join("b.cwim"), "y = 2").unwrap();
+        std::fs::write(dir.join("c.cwim"), "include \"c.cwim\"").unwrap();
+        assert_eq!(
+            texts(load(&dir.join("a.cwim")).unwrap()),
+            vec!["x = 1", "y = 2", "x + y"]
+        );
+        assert!(load(&dir.join("c.cwim")).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
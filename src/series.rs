@@ -0,0 +1,326 @@
+use num::{BigInt, One, ToPrimitive, Zero};
+
+use crate::function::{ADD, DIV, MUL, POW, SUB};
+use crate::number::Number;
+use crate::parser::Parsed;
+use crate::polynomial::Polynomial;
+use crate::s::{eval_with, Scope, S};
+
+// Sums and products of a term over k = from..to, e.g. sum(k^2, k, 1, 100)
+
+// Beyond this, sums and products that can't be shortened are too slow
+const MAX_TERMS: u32 = 100_000;
+// Beyond this, fractions get too long to add exactly, e.g. in the sum of 1/k,
+// and products too long to multiply, so they go on as floats
+const MAX_EXACT_TERMS: usize = 1_000;
+// The terms of infinite series used to estimate their limit
+const MAX_SERIES_TERMS: usize = 60;
+
+// The value of `term` for k = n, with the other names bound around it
+fn at(term: &S, k: &str, n: &BigInt, scope: &Scope) -> Parsed<Number> {
+    scope.at(term, k, Number::Int(n.clone()))
+}
+
+// The bounds as integers, the upper one being None for infinity
+fn bounds(from: &Number, to: &Number) -> Parsed<(BigInt, Option<BigInt>)> {
+    let from = match from {
+        Number::Int(n) => n.clone(),
+        n => return Err(format!("Expected an integer to start from, found {}", n)),
+    };
+    match to {
+        Number::Int(n) => Ok((from, Some(n.clone()))),
+        Number::Flt(x) if x.is_infinite() && x.is_sign_positive() => Ok((from, None)),
+        n => Err(format!("Expected an integer or inf to end at, found {}", n)),
+    }
+}
+
+// Totals are wrapped to the word after every term, like any other addition
+pub fn sum(term: &S, k: &str, from: &Number, to: &Number, scope: &Scope) -> Parsed<Number> {
+    let (from, to) = bounds(from, to)?;
+    let Some(to) = to else {
+        let terms = (0u32..).map(|i| {
+            let term = at(term, k, &(&from + i), scope)?;
+            Ok(term.to_f64().unwrap_or(f64::NAN))
+        });
+        let mut total = 0.;
+        let partials = terms.map(|it: Parsed<f64>| {
+            let term = it?;
+            total += term;
+            Ok((total, term))
+        });
+        return limit(&from, partials).map(Number::Flt);
+    };
+    if to < from {
+        return Ok(Number::zero());
+    }
+    match degree(term, k) {
+        // Only worth it when there are more terms than points to interpolate
+        Some(d) if &to - &from > BigInt::from(d + 2) => {
+            let total = closed_form(term, k, &from, "", d, scope)?.at(&Number::Int(to));
+            Ok(scope.result(&ADD, total))
+        }
+        _ => {
+            let mut total = Number::zero();
+            for (i, n) in range(&from, &to)?.enumerate() {
+                if i == MAX_EXACT_TERMS && !matches!(total, Number::Int(_)) {
+                    total = Number::Flt(total.f64_or_nan());
+                }
+                total = scope.result(&ADD, total + at(term, k, &n, scope)?);
+            }
+            Ok(total)
+        }
+    }
+}
+
+pub fn prod(term: &S, k: &str, from: &Number, to: &Number, scope: &Scope) -> Parsed<Number> {
+    let (from, to) = bounds(from, to)?;
+    let Some(to) = to else {
+        let mut total = 1.;
+        let partials = (0u32..).map(|i| {
+            let factor = at(term, k, &(&from + i), scope)?;
+            let last = total;
+            total *= factor.to_f64().unwrap_or(f64::NAN);
+            Ok((total, total - last))
+        });
+        return limit(&from, partials).map(Number::Flt);
+    };
+    let mut total = Number::one();
+    for (i, n) in range(&from, &to)?.enumerate() {
+        if i == MAX_EXACT_TERMS {
+            total = Number::Flt(total.f64_or_nan());
+        }
+        total = scope.result(&MUL, total * at(term, k, &n, scope)?);
+        if total.is_zero() {
+            break;
+        }
+    }
+    Ok(total)
+}
+
+fn range(from: &BigInt, to: &BigInt) -> Parsed<impl Iterator<Item = BigInt>> {
+    let count = (to - from + 1u32).to_u32().filter(|it| *it <= MAX_TERMS);
+    let count =
+        count.ok_or_else(|| format!("Can't add or multiply more than {} terms", MAX_TERMS))?;
+    let from = from.clone();
+    Ok((0..count).map(move |i| &from + i))
+}
+
+// Roughly how many terms are evaluated for these bounds, e.g. for sums up
+// to an unknown n as many as the degree of the closed form
+pub fn count(from: &S, to: &S) -> usize {
+    let number = |s: &S| match s {
+        S::Var(n) => Some(n.clone()),
+        S::Name(_, s) => match s.as_ref() {
+            S::Var(n) => Some(n.clone()),
+            _ => None,
+        },
+        _ => None,
+    };
+    match (number(from), number(to)) {
+        _ if matches!(to, S::Unknown(_)) => 100,
+        (Some(Number::Int(from)), Some(Number::Int(to))) => (to - from + 1u32)
+            .to_usize()
+            .map_or(0, |it| it.min(MAX_TERMS as usize)),
+        (Some(_), Some(Number::Flt(x))) if x.is_infinite() => MAX_SERIES_TERMS,
+        _ => usize::MAX,
+    }
+}
+
+// The degree of `term` as a polynomial in k, if it is one
+pub fn degree(term: &S, k: &str) -> Option<u32> {
+    match term {
+        S::Var(_) | S::Value(_) | S::Text(_) => Some(0),
+        S::Unknown(x) | S::Name(x, _) => Some(u32::from(*x == k)),
+        S::Fun(f, args) if (*f == ADD || *f == SUB) => args
+            .iter()
+            .map(|it| degree(it, k))
+            .try_fold(0, |a, b| Some(a.max(b?))),
+        S::Fun(f, args) if *f == MUL => args
+            .iter()
+            .map(|it| degree(it, k))
+            .try_fold(0, |a, b| Some(a + b?)),
+        S::Fun(f, args) if *f == DIV && args.len() == 2 => match degree(&args[1], k)? {
+            0 => degree(&args[0], k),
+            _ => None,
+        },
+        S::Fun(f, args) if *f == POW && args.len() == 2 => {
+            let base = degree(&args[0], k)?;
+            match (base, degree(&args[1], k)?) {
+                (0, 0) => Some(0),
+                (base, 0) => match eval_with(&args[1], &|_| None).ok()? {
+                    Number::Int(n) => base.checked_mul(n.to_u32()?).filter(|it| *it <= 100),
+                    _ => None,
+                },
+                _ => None,
+            }
+        }
+        // Anything else is a constant if k isn't in it, e.g. sqrt(2)
+        S::Fun(_, args) | S::Call(_, args, _) | S::List(args) | S::Form(_, args) => {
+            args.iter().all(|it| degree(it, k) == Some(0)).then_some(0)
+        }
+    }
+}
+
+// The sum of `term` for k from `from` to n, as a polynomial in n. Sums of
+// polynomials of degree d are polynomials of degree d + 1, as in Faulhaber's
+// formula, so it's the one through the sums up to from - 1, from, ... from + d.
+pub fn closed_form(
+    term: &S,
+    k: &str,
+    from: &BigInt,
+    n: &str,
+    degree: u32,
+    scope: &Scope,
+) -> Parsed<Polynomial> {
+    let mut xs = vec![Number::Int(from - 1)];
+    let mut ys = vec![Number::zero()];
+    for i in 0..=degree {
+        let k_value = from + i;
+        let total = ys[ys.len() - 1].clone() + at(term, k, &k_value, scope)?;
+        xs.push(Number::Int(k_value));
+        ys.push(total);
+    }
+    Polynomial::fit(n, &xs, &ys, degree as usize + 1)
+}
+
+// Terms have to go to 0, and faster than 1/k when they keep their sign, as the
+// sum of 1/k grows without bound. `terms` start at k = from.
+fn diverges(from: f64, terms: &[f64]) -> bool {
+    let (i, j) = (terms.len() / 2, terms.len() - 1);
+    let (a, b) = (terms[i].abs(), terms[j].abs());
+    let same_sign = terms[i..].windows(2).all(|it| it[0] * it[1] > 0.);
+    b > 0. && (b >= a || same_sign && (from + j as f64) * b > 0.9 * (from + i as f64) * a)
+}
+
+// The limit of partial sums, given with their last term, using Levin's u
+// transform, which speeds up both alternating series and slow ones like the
+// sum of 1/k^2. The version from Numerical Recipes, 3rd edition, 5.3.
+fn limit(from: &BigInt, partials: impl Iterator<Item = Parsed<(f64, f64)>>) -> Parsed<f64> {
+    let partials = partials
+        .take(MAX_SERIES_TERMS)
+        .collect::<Parsed<Vec<_>>>()?;
+    let terms: Vec<_> = partials.iter().map(|(_, term)| *term).collect();
+    let diverges = diverges(from.to_f64().unwrap_or(f64::NAN), &terms);
+    if diverges || partials.iter().any(|(total, _)| !total.is_finite()) {
+        return Err("The series doesn't converge".to_owned());
+    }
+    let (mut numer, mut denom) = (vec![], vec![]);
+    let (mut last, mut best) = (f64::NAN, (f64::INFINITY, f64::NAN));
+    let mut converged = 0;
+    for (i, (total, term)) in partials.into_iter().enumerate() {
+        // Terms that are 0 leave the sum as it is
+        if term == 0. {
+            if numer.is_empty() || total == last {
+                last = total;
+                best = (0., total);
+            }
+            continue;
+        }
+        let n = numer.len();
+        let beta = 1.;
+        let mut t = 1. / (beta + n as f64);
+        denom.push(t / ((beta + n as f64) * term));
+        numer.push(total * denom[n]);
+        let ratio = (beta + n as f64 - 1.) * t;
+        for j in 1..=n {
+            let fact = (n - j) as f64 * t + beta * t;
+            numer[n - j] = numer[n - j + 1] - fact * numer[n - j];
+            denom[n - j] = denom[n - j + 1] - fact * denom[n - j];
+            t *= ratio;
+        }
+        let value = numer[0] / denom[0];
+        let change = (value - last).abs();
+        last = value;
+        if !change.is_finite() {
+            continue;
+        }
+        if change < best.0 && i > 2 {
+            best = (change, value);
+        }
+        if change <= 1e-14 * value.abs() && i > 8 {
+            converged += 1;
+            if converged == 2 {
+                return Ok(value);
+            }
+        }
+    }
+    match best {
+        (change, value) if change <= 1e-6 * value.abs().max(1.) => Ok(value),
+        _ => Err("The series doesn't seem to converge".to_owned()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::env::Env;
+    use crate::parser;
+    use crate::pratt;
+
+    fn series(
+        f: fn(&S, &str, &Number, &Number, &Scope) -> Parsed<Number>,
+        text: &str,
+        to: Number,
+    ) -> Parsed<Number> {
+        let env = Env::prelude();
+        let mut tokens = parser::stmt(text, &env)?.rhs().clone();
+        let term = pratt::expr(&mut tokens, &env)?;
+        Scope::empty(|scope| f(&term, "k", &Number::from(1), &to, scope))
+    }
+
+    #[test]
+    fn _degree() {
+        let env = Env::prelude();
+        let degree_of = |text: &str| {
+            let mut tokens = parser::stmt(text, &env).unwrap().rhs().clone();
+            degree(&pratt::expr(&mut tokens, &env).unwrap(), "k")
+        };
+        assert_eq!(degree_of("3k^2 + k/2 - 1"), Some(2));
+        assert_eq!(degree_of("(k + 1)(k - 1)k"), Some(3));
+        assert_eq!(degree_of("sqrt(2) k"), Some(1));
+        assert_eq!(degree_of("1/k"), None);
+        assert_eq!(degree_of("2^k"), None);
+    }
+
+    #[test]
+    fn _sum() {
+        let ratio = |n: i64, d: i64| Number::from(n) / Number::from(d);
+        assert_eq!(
+            series(sum, "k^2", Number::from(100)),
+            Ok(Number::from(338350))
+        );
+        assert_eq!(
+            series(sum, "k^3", Number::from(1_000_000_000_000i64)),
+            Ok(Number::Int(
+                "250000000000500000000000250000000000000000000000"
+                    .parse()
+                    .unwrap()
+            ))
+        );
+        assert_eq!(series(sum, "1/k", Number::from(4)), Ok(ratio(25, 12)));
+        assert_eq!(
+            series(prod, "1 + 1/k", Number::from(9)),
+            Ok(Number::from(10))
+        );
+        assert_eq!(series(sum, "k", Number::from(0)), Ok(Number::from(0)));
+        // Long sums of fractions go on as floats
+        let harmonic = series(sum, "1/k", Number::from(20000)).unwrap();
+        assert!(matches!(harmonic, Number::Flt(x) if (x - 10.480728217229322).abs() < 1e-9));
+    }
+
+    #[test]
+    fn _series() {
+        let inf = Number::Flt(f64::INFINITY);
+        let close = |x: Parsed<Number>, y: f64| {
+            let x = x.unwrap().to_f64().unwrap();
+            assert!((x - y).abs() < 1e-9 * y.abs(), "{} != {}", x, y);
+        };
+        close(
+            series(sum, "1/k^2", inf.clone()),
+            std::f64::consts::PI.powi(2) / 6.,
+        );
+        close(series(sum, "(-1)^(k+1)/k", inf.clone()), 2f64.ln());
+        close(series(sum, "1/2^k", inf.clone()), 1.);
+        assert!(series(sum, "1", inf.clone()).is_err());
+    }
+}
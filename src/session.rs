@@ -0,0 +1,263 @@
+use std::path::{Path, PathBuf};
+
+use num::{rational::Ratio, BigInt};
+
+use crate::currency;
+use crate::env::{Env, Variable};
+use crate::number::Number;
+use crate::parser::Parsed;
+use crate::polynomial::Polynomial;
+use crate::time;
+use crate::value::Value;
+
+// The session the REPL saves on exit and loads on start when `autorestore` is on.
+pub const AUTOSAVE: &str = ".cwim_session";
+
+// `:save name` writes to `name.cwim-session`, unless `name` already has an extension.
+pub fn path(name: &str) -> PathBuf {
+    let path = PathBuf::from(name);
+    if path.extension().is_some() {
+        path
+    } else {
+        path.with_extension("cwim-session")
+    }
+}
+
+pub fn save(env: &Env, path: &Path) -> Parsed<()> {
+    std::fs::write(path, to_string(env))
+        .map_err(|e| format!("Can't write '{}': {}", path.display(), e))
+}
+
+pub fn load(env: &mut Env, path: &Path) -> Parsed<()> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Can't read '{}': {}", path.display(), e))?;
+    restore(env, &text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// Sessions are written as sections of `key = value` lines, e.g.
+//
+// [settings]
+// autorestore = on
+//
+// [values]
+// x = 1/3
+//
+// [definitions]
+// area := w * h
+//
+// [operators]
+// <+> 5 left (a, b) -> sqrt(a^2+b^2)
+pub fn to_string(env: &Env) -> String {
+    let mut settings = String::new();
+    for (key, value) in env.settings.entries() {
+        settings += &format!("{} = {}\n", key, value);
+    }
+    let mut values = String::new();
+    let mut definitions = String::new();
+    for (name, var) in env.user_variables() {
+        match var {
+            Variable::Value(v) => values += &format!("{} = {}\n", name, value_to_string(v)),
+            Variable::Lazy(body) => definitions += &format!("{} := {}\n", name, body),
+            Variable::Function(_) => {}
+        }
+    }
+    let mut operators = String::new();
+    for declaration in env.operators() {
+        operators += &format!("{}\n", declaration);
+    }
+    format!(
+        "# cwim session\n[settings]\n{}\n[values]\n{}\n[definitions]\n{}\n[operators]\n{}",
+        settings, values, definitions, operators
+    )
+}
+
+pub fn restore(env: &mut Env, text: &str) -> Parsed<()> {
+    let mut section = "";
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|it| it.strip_suffix(']')) {
+            section = name;
+            continue;
+        }
+        let result = match section {
+            "settings" => split(line, "=").and_then(|(k, v)| env.settings.set(k, v)),
+            "values" => split(line, "=").and_then(|(k, v)| {
+                env.assign(k.to_owned(), value_from_str(v)?);
+                Ok(())
+            }),
+            "definitions" => split(line, ":=").and_then(|(k, v)| env.define(k, v).map(|_| ())),
+            "operators" => env.infix(line),
+            _ => Err(format!("unknown section '{}'", section)),
+        };
+        result.map_err(|e| format!("line {}: {}", i + 1, e))?;
+    }
+    Ok(())
+}
+
+fn split<'a>(line: &'a str, separator: &str) -> Parsed<(&'a str, &'a str)> {
+    match line.split_once(separator) {
+        Some((k, v)) => Ok((k.trim(), v.trim())),
+        None => Err(format!("expected '{}' in '{}'", separator, line)),
+    }
+}
+
+// Unlike Display, this keeps floats apart from ints, e.g. 1.0 and 1
+fn number_to_string(n: &Number) -> String {
+    match n {
+        Number::Int(n) => n.to_string(),
+        Number::Rat(n) => n.to_string(),
+        Number::Flt(n) => format!("{:?}", n),
+    }
+}
+
+// Dates as typed, and durations in seconds and money unrounded so that
+// fractions are kept exact, e.g. 1/3 s or 10/3 USD
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::Number(n) => number_to_string(n),
+        Value::Date(_) => value.to_string(),
+        Value::Duration(t) => format!("{} s", number_to_string(t)),
+        Value::Money(amount, code) => format!("{} {}", amount, code),
+        Value::List(items) => {
+            let items: Vec<_> = items.iter().map(number_to_string).collect();
+            format!("{{{}}}", items.join(", "))
+        }
+        Value::Poly(p) => {
+            let terms: Vec<_> = p
+                .terms()
+                .map(|(powers, c)| {
+                    let powers = powers.iter().map(|(x, k)| format!("*{}^{}", x, k));
+                    number_to_string(c) + &powers.collect::<String>()
+                })
+                .collect();
+            format!("poly {}", terms.join(" + "))
+        }
+    }
+}
+
+fn value_from_str(text: &str) -> Parsed<Value> {
+    if let Some(items) = text.strip_prefix('{').and_then(|it| it.strip_suffix('}')) {
+        let items = items.split(',').map(str::trim).filter(|it| !it.is_empty());
+        return items
+            .map(number_from_str)
+            .collect::<Parsed<_>>()
+            .map(Value::List);
+    }
+    if let Some(terms) = text.strip_prefix("poly ") {
+        let mut p = Polynomial::default();
+        for term in terms.split(" + ") {
+            let mut factors = term.split('*');
+            let c = number_from_str(factors.next().unwrap_or_default())?;
+            let mut term = Polynomial::constant(c);
+            for power in factors {
+                let (x, k) = power
+                    .split_once('^')
+                    .and_then(|(x, k)| Some((x, k.parse().ok()?)))
+                    .ok_or_else(|| format!("'{}' is not a power", power))?;
+                term *= &Polynomial::variable(x).pow(k);
+            }
+            p += &term;
+        }
+        return Ok(Value::from(p));
+    }
+    if let Some(seconds) = text.strip_suffix(" s") {
+        return Ok(Value::Duration(number_from_str(seconds)?));
+    }
+    if let Some((amount, code)) = text
+        .rsplit_once(' ')
+        .filter(|(_, it)| currency::is_code(it))
+    {
+        let amount = amount
+            .parse()
+            .map_err(|_| format!("'{}' is not an amount", amount))?;
+        return Ok(Value::Money(amount, code.to_owned()));
+    }
+    match time::date(text) {
+        Some(Ok((seconds, length))) if length == text.len() => Ok(Value::Date(seconds)),
+        _ => number_from_str(text).map(Value::Number),
+    }
+}
+
+fn number_from_str(text: &str) -> Parsed<Number> {
+    if let Ok(n) = text.parse::<BigInt>() {
+        Ok(Number::Int(n))
+    } else if let Ok(n) = text.parse::<Ratio<BigInt>>() {
+        Ok(Number::Rat(n))
+    } else if let Ok(n) = text.parse::<f64>() {
+        Ok(Number::Flt(n))
+    } else {
+        Err(format!("'{}' is not a number", text))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::interpreter::{run, run_stmt, run_value};
+
+    #[test]
+    fn _round_trip() {
+        let mut env = Env::prelude();
+        run("x = 1/3", &mut env).unwrap();
+        run("y = 2.", &mut env).unwrap();
+        run_value("start = 2026-10-17T08:00", &mut env).unwrap();
+        run_value("slot = 1 week / 3", &mut env).unwrap();
+        run_value("fee = 10 EUR / 3", &mut env).unwrap();
+        run_value("xs = {1/3, 2., 4}", &mut env).unwrap();
+        run_value("p = poly(a^2 b/2 - b/2.)", &mut env).unwrap();
+        run_stmt("area := x * w", &mut env).unwrap();
+        env.infix("// 6 (a, b) -> (a - a%b)/b").unwrap();
+        env.settings.autorestore = true;
+        let text = to_string(&env);
+        assert!(!text.contains("pi"));
+
+        let mut restored = Env::prelude();
+        restore(&mut restored, &text).unwrap();
+        assert_eq!(to_string(&restored), text);
+        assert!(restored.settings.autorestore);
+        assert_eq!(run("y", &mut restored), Ok(Number::Flt(2.)));
+        assert_eq!(run("w = 6", &mut restored), Ok(Number::from(6)));
+        assert_eq!(run("area", &mut restored), Ok(Number::from(2)));
+        assert_eq!(run("7 // 2", &mut restored), Ok(Number::from(3)));
+        let end = run_value("start + 3 slot", &mut restored).map(|it| it.to_string());
+        assert_eq!(end.as_deref(), Ok("2026-10-24T08:00"));
+        let fees = run_value("3 fee", &mut restored).map(|it| it.to_string());
+        assert_eq!(fees.as_deref(), Ok("10.00 EUR"));
+        assert_eq!(
+            run("sum xs", &mut restored),
+            Ok(Number::Flt(1. / 3. + 2. + 4.))
+        );
+        let p = run_value("p", &mut restored).map(|it| it.to_string());
+        assert_eq!(p.as_deref(), Ok("1/2·a²·b - 0.5·b"));
+    }
+
+    #[test]
+    fn _built_in_operators() {
+        // Bit operations can be replaced, but not the other built in operators
+        let mut env = Env::prelude();
+        restore(&mut env, "[operators]\n<< 3 left (x, n) -> x*2^n").unwrap();
+        assert_eq!(run("1+1 << 2+1", &mut env), Ok(Number::from(16)));
+        assert_eq!(
+            restore(&mut env, "[operators]\n+ 3 left (x, n) -> x"),
+            Err("line 2: Can't redefine '+'".to_owned())
+        );
+    }
+
+    #[test]
+    fn _bad_line() {
+        let mut env = Env::prelude();
+        assert_eq!(
+            restore(&mut env, "[values]\nx = y"),
+            Err("line 2: 'y' is not a number".to_owned())
+        );
+    }
+
+    #[test]
+    fn _path() {
+        assert_eq!(path("work"), PathBuf::from("work.cwim-session"));
+        assert_eq!(path("work.txt"), PathBuf::from("work.txt"));
+    }
+}
@@ -0,0 +1,130 @@
+use crate::number::Number;
+use crate::parser::Parsed;
+use crate::value::Value;
+use crate::word::Word;
+
+// Options that change how cwim behaves, set with `:set key value`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    // Save the session when the REPL exits and load it again on start
+    pub autorestore: bool,
+    // Don't print the value of statements ending in `;`
+    pub suppress: bool,
+    // Read SI prefixes after numbers, e.g. 4.7k is 4700 and 3n is 3e-9
+    pub si: bool,
+    // The base values are printed in, 2 to 36, or 60 for 12:30:15
+    pub base: u32,
+    // The word integers are wrapped to, set with `:bits 32 signed`
+    pub word: Option<Word>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            autorestore: false,
+            suppress: false,
+            si: false,
+            base: 10,
+            word: None,
+        }
+    }
+}
+
+impl Settings {
+    pub fn get(&self, key: &str) -> Parsed<String> {
+        match key {
+            "autorestore" => Ok(flag(self.autorestore)),
+            "suppress" => Ok(flag(self.suppress)),
+            "si" => Ok(flag(self.si)),
+            "base" => Ok(self.base.to_string()),
+            "bits" => Ok(self.word.map_or("off".to_owned(), |it| it.to_string())),
+            _ => Err(format!("Unknown setting '{}'", key)),
+        }
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) -> Parsed<()> {
+        match key {
+            "autorestore" => self.autorestore = parse_flag(value)?,
+            "suppress" => self.suppress = parse_flag(value)?,
+            "si" => self.si = parse_flag(value)?,
+            "base" => self.base = parse_base(value)?,
+            "bits" if value == "off" => self.word = None,
+            "bits" => self.word = Some(Word::parse(value)?),
+            _ => return Err(format!("Unknown setting '{}'", key)),
+        }
+        Ok(())
+    }
+
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("autorestore", flag(self.autorestore)),
+            ("suppress", flag(self.suppress)),
+            ("si", flag(self.si)),
+            ("base", self.base.to_string()),
+            ("bits", self.get("bits").expect("bits is a setting")),
+        ]
+    }
+
+    // How a value is printed, in two's complement when there's a word
+    pub fn show(&self, n: &Number) -> String {
+        if let Some(text) = self.word.and_then(|it| it.show(n, self.base)) {
+            return text;
+        }
+        match self.base {
+            10 => n.to_string(),
+            radix => n.to_string_radix(radix),
+        }
+    }
+
+    // Numbers in the base, and dates and durations as they're typed
+    pub fn show_value(&self, value: &Value) -> String {
+        match value {
+            Value::Number(n) => self.show(n),
+            Value::List(items) => {
+                let items: Vec<_> = items.iter().map(|it| self.show(it)).collect();
+                format!("{{{}}}", items.join(", "))
+            }
+            value => value.to_string(),
+        }
+    }
+}
+
+fn parse_base(value: &str) -> Parsed<u32> {
+    match value.parse() {
+        Ok(base @ (2..=36 | 60)) => Ok(base),
+        _ => Err(format!(
+            "Expected a base from 2 to 36 or 60, found '{}'",
+            value
+        )),
+    }
+}
+
+fn flag(value: bool) -> String {
+    if value { "on" } else { "off" }.to_owned()
+}
+
+fn parse_flag(value: &str) -> Parsed<bool> {
+    match value {
+        "on" | "true" | "yes" | "1" => Ok(true),
+        "off" | "false" | "no" | "0" => Ok(false),
+        _ => Err(format!("Expected on or off, found '{}'", value)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn _set() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.get("autorestore"), Ok("off".to_owned()));
+        settings.set("autorestore", "true").unwrap();
+        assert_eq!(settings.get("autorestore"), Ok("on".to_owned()));
+        assert!(settings.set("autorestore", "maybe").is_err());
+        assert!(settings.set("colour", "on").is_err());
+        settings.set("base", "16").unwrap();
+        assert_eq!(settings.show(&Number::from(255)), "0xff");
+        assert!(settings.set("base", "37").is_err());
+    }
+}
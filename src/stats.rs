@@ -0,0 +1,154 @@
+use std::cmp::Ordering;
+
+use num::{ToPrimitive, Zero};
+
+use crate::number::Number;
+
+// Statistics of lists, exact for integers and rationals. Those that aren't
+// defined for the data, e.g. the mean of nothing, are NaN.
+
+fn nan() -> Number {
+    Number::Flt(f64::NAN)
+}
+
+// None if one of them is NaN, which has no place in the order
+fn sorted(xs: &[Number]) -> Option<Vec<Number>> {
+    if xs.iter().any(Number::is_nan) {
+        return None;
+    }
+    let mut xs = xs.to_vec();
+    xs.sort_by(|x, y| x.partial_cmp(y).unwrap_or(Ordering::Equal));
+    Some(xs)
+}
+
+pub fn count(xs: &[Number]) -> Number {
+    Number::from(xs.len())
+}
+
+pub fn sum(xs: &[Number]) -> Number {
+    xs.iter().cloned().fold(Number::zero(), |a, b| a + b)
+}
+
+pub fn mean(xs: &[Number]) -> Number {
+    match xs.len() {
+        0 => nan(),
+        n => sum(xs) / Number::from(n),
+    }
+}
+
+pub fn median(xs: &[Number]) -> Number {
+    quantile(xs, Number::from(1) / Number::from(2))
+}
+
+// The most frequent value, the smallest one if there are several
+pub fn mode(xs: &[Number]) -> Number {
+    let Some(xs) = sorted(xs) else {
+        return nan();
+    };
+    let mut best: Option<(&Number, usize)> = None;
+    let mut i = 0;
+    while i < xs.len() {
+        let run = xs[i..].iter().take_while(|it| **it == xs[i]).count();
+        if best.is_none_or(|(_, count)| run > count) {
+            best = Some((&xs[i], run));
+        }
+        i += run;
+    }
+    best.map_or_else(nan, |(x, _)| x.clone())
+}
+
+// The sample variance, dividing by n - 1
+pub fn var(xs: &[Number]) -> Number {
+    if xs.len() < 2 {
+        return nan();
+    }
+    let mean = mean(xs);
+    let squares = xs.iter().map(|x| {
+        let d = x.clone() - mean.clone();
+        d.clone() * d
+    });
+    squares.fold(Number::zero(), |a, b| a + b) / Number::from(xs.len() - 1)
+}
+
+pub fn stdev(xs: &[Number]) -> Number {
+    Number::Flt(var(xs).f64_or_nan().sqrt())
+}
+
+pub fn min(xs: &[Number]) -> Number {
+    sorted(xs)
+        .and_then(|it| it.into_iter().next())
+        .unwrap_or_else(nan)
+}
+
+pub fn max(xs: &[Number]) -> Number {
+    sorted(xs).and_then(|mut it| it.pop()).unwrap_or_else(nan)
+}
+
+// Interpolates linearly between the closest values, like spreadsheets do,
+// so that the 0.5 quantile is the median
+pub fn quantile(xs: &[Number], q: Number) -> Number {
+    let zero = Number::zero();
+    if xs.is_empty() || q < zero || q > Number::from(1) || q.is_nan() {
+        return nan();
+    }
+    let Some(xs) = sorted(xs) else {
+        return nan();
+    };
+    let h = q * Number::from(xs.len() - 1);
+    let Some(i) = h.to_f64().and_then(|it| it.floor().to_usize()) else {
+        return nan();
+    };
+    let fraction = h - Number::from(i);
+    match xs.get(i + 1) {
+        Some(next) if !fraction.is_zero() => {
+            xs[i].clone() + fraction * (next.clone() - xs[i].clone())
+        }
+        _ => xs[i].clone(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn numbers(xs: &[i64]) -> Vec<Number> {
+        xs.iter().map(|x| Number::from(*x)).collect()
+    }
+
+    #[test]
+    fn _exact() {
+        let xs = numbers(&[3, 1, 4, 1, 5, 9, 2, 6]);
+        assert_eq!(sum(&xs), Number::from(31));
+        assert_eq!(mean(&xs), Number::from(31) / Number::from(8));
+        assert_eq!(median(&xs), Number::from(7) / Number::from(2));
+        assert_eq!(mode(&xs), Number::from(1));
+        assert_eq!(
+            var(&numbers(&[1, 2, 3, 4])),
+            Number::from(5) / Number::from(3)
+        );
+        assert_eq!(min(&xs), Number::from(1));
+        assert_eq!(max(&xs), Number::from(9));
+        assert_eq!(quantile(&xs, Number::from(1)), Number::from(9));
+        assert_eq!(
+            quantile(&numbers(&[0, 10]), Number::from(1) / Number::from(4)),
+            Number::from(5) / Number::from(2)
+        );
+        assert_eq!(count(&xs), Number::from(8));
+    }
+
+    #[test]
+    fn _undefined() {
+        assert!(mean(&[]).is_nan());
+        assert!(var(&numbers(&[1])).is_nan());
+        assert!(quantile(&numbers(&[1, 2]), Number::from(2)).is_nan());
+        assert_eq!(
+            stdev(&numbers(&[2, 4, 4, 4, 5, 5, 7, 9])).to_f64(),
+            Some((32f64 / 7.).sqrt())
+        );
+        let mut xs = numbers(&[1, 2, 3]);
+        xs.push(nan());
+        for f in [median, mode, min, max] {
+            assert!(f(&xs).is_nan());
+        }
+    }
+}
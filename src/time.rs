@@ -0,0 +1,224 @@
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use num::rational::Ratio;
+use num::{BigInt, Signed, ToPrimitive, Zero};
+
+use crate::number::Number;
+use crate::parser::Parsed;
+use crate::value::Value;
+
+// Gives the seconds since 1970-01-01T00:00:00 UTC for `now`, so that tests
+// can use a fixed time
+pub type Clock = Arc<dyn Fn() -> Number + Send + Sync>;
+
+pub fn system_clock() -> Number {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    Number::from(elapsed.as_secs())
+}
+
+const MINUTE: i64 = 60;
+const HOUR: i64 = 60 * MINUTE;
+const DAY: i64 = 24 * HOUR;
+
+// Names that stand for durations when they aren't variables, e.g. 90 days,
+// with their length in milliseconds
+const UNITS: [(&str, i64); 19] = [
+    ("ms", 1),
+    ("s", 1000),
+    ("sec", 1000),
+    ("secs", 1000),
+    ("second", 1000),
+    ("seconds", 1000),
+    ("min", MINUTE * 1000),
+    ("mins", MINUTE * 1000),
+    ("minute", MINUTE * 1000),
+    ("minutes", MINUTE * 1000),
+    ("h", HOUR * 1000),
+    ("hr", HOUR * 1000),
+    ("hour", HOUR * 1000),
+    ("hours", HOUR * 1000),
+    ("day", DAY * 1000),
+    ("days", DAY * 1000),
+    ("week", 7 * DAY * 1000),
+    ("weeks", 7 * DAY * 1000),
+    ("wk", 7 * DAY * 1000),
+];
+
+pub fn unit(name: &str) -> Option<Value> {
+    let (_, ms) = UNITS.iter().find(|(it, _)| *it == name)?;
+    Some(Value::Duration(Number::from(*ms) / Number::from(1000)))
+}
+
+// Days since 1970-01-01, from
+// https://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+// The year, month and day of a number of days since 1970-01-01
+fn civil_from_days(z: i64) -> (i64, i64, i64) {
+    let z = z + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    (
+        if m <= 2 {
+            yoe + era * 400 + 1
+        } else {
+            yoe + era * 400
+        },
+        m,
+        d,
+    )
+}
+
+fn days_in_month(y: i64, m: i64) -> i64 {
+    let next = if m == 12 { (y + 1, 1) } else { (y, m + 1) };
+    days_from_civil(next.0, next.1, 1) - days_from_civil(y, m, 1)
+}
+
+// A date such as 2026-10-17 or 2026-10-17T13:45:30 at the start of `text`,
+// as seconds since 1970-01-01 UTC, and its length. None if `text` doesn't
+// start with a date.
+pub fn date(text: &str) -> Option<Parsed<(Number, usize)>> {
+    let field = |start: usize, len: usize| -> Option<i64> {
+        let digits = text.get(start..start + len)?;
+        digits
+            .bytes()
+            .all(|b| b.is_ascii_digit())
+            .then(|| digits.parse().ok())?
+    };
+    let separated = |i: usize, c: u8| text.as_bytes().get(i) == Some(&c);
+    let (y, m, d) = (field(0, 4)?, field(5, 2)?, field(8, 2)?);
+    if !separated(4, b'-') || !separated(7, b'-') {
+        return None;
+    }
+    let (mut length, mut seconds) = (10, 0);
+    if separated(10, b'T') && separated(13, b':') {
+        let (h, min) = (field(11, 2)?, field(14, 2)?);
+        let s = match separated(16, b':') {
+            true => field(17, 2)?,
+            false => 0,
+        };
+        // Only digits and separators so far, so this is a char boundary
+        length = if separated(16, b':') { 19 } else { 16 };
+        if h >= 24 || min >= 60 || s >= 60 {
+            return Some(Err(format!("'{}' isn't a time of day", &text[..length])));
+        }
+        seconds = h * HOUR + min * MINUTE + s;
+    }
+    if text[length..].starts_with(|c: char| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    if !(1..=12).contains(&m) || d < 1 || d > days_in_month(y, m) {
+        return Some(Err(format!("'{}' isn't a date", &text[..10])));
+    }
+    let days = days_from_civil(y, m, d);
+    Some(Ok((Number::from(days * DAY + seconds), length)))
+}
+
+fn exact(n: &Number) -> Option<Ratio<BigInt>> {
+    match n {
+        Number::Int(n) => Some(Ratio::from_integer(n.clone())),
+        Number::Rat(r) => Some(r.clone()),
+        Number::Flt(x) => Ratio::from_float(*x),
+    }
+}
+
+// 2026-10-17, or 2026-10-17T13:45:30 when it isn't midnight
+pub fn format_date(seconds: &Number) -> String {
+    let Some(seconds) = exact(seconds).and_then(|it| it.floor().to_integer().to_i64()) else {
+        return format!("date({})", seconds);
+    };
+    let (y, m, d) = civil_from_days(seconds.div_euclid(DAY));
+    let time = seconds.rem_euclid(DAY);
+    let (h, min, s) = (time / HOUR, time % HOUR / MINUTE, time % MINUTE);
+    match (time, s) {
+        (0, _) => format!("{:04}-{:02}-{:02}", y, m, d),
+        (_, 0) => format!("{:04}-{:02}-{:02}T{:02}:{:02}", y, m, d, h, min),
+        _ => format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}", y, m, d, h, min, s),
+    }
+}
+
+// 2 days 3h 25min 10s, which reads back as the same duration
+pub fn format_duration(seconds: &Number) -> String {
+    let Some(total) = exact(seconds) else {
+        return format!("{} s", seconds);
+    };
+    if total.is_negative() {
+        return format!("-({})", format_duration(&Number::Rat(-total)));
+    }
+    let whole = total.trunc().to_integer();
+    let fraction = total.fract();
+    let part = |unit: i64, of: i64| (&whole / BigInt::from(unit)) % BigInt::from(of);
+    let days = &whole / BigInt::from(DAY);
+    let mut parts = vec![];
+    match days.to_i64() {
+        Some(0) => {}
+        Some(1) => parts.push("1 day".to_owned()),
+        _ => parts.push(format!("{} days", days)),
+    }
+    for (value, unit) in [(part(HOUR, 24), "h"), (part(MINUTE, 60), "min")] {
+        if !value.is_zero() {
+            parts.push(format!("{}{}", value, unit));
+        }
+    }
+    let s = part(1, 60);
+    if !fraction.is_zero() {
+        let s = (Ratio::from_integer(s) + fraction)
+            .to_f64()
+            .unwrap_or(f64::NAN);
+        parts.push(format!("{}s", s));
+    } else if !s.is_zero() || parts.is_empty() {
+        parts.push(format!("{}s", s));
+    }
+    parts.join(" ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn _date() {
+        assert_eq!(date("1970-01-01"), Some(Ok((Number::from(0), 10))));
+        let (seconds, length) = date("2026-10-17T13:45 + 1").unwrap().unwrap();
+        assert_eq!(length, 16);
+        assert_eq!(format_date(&seconds), "2026-10-17T13:45");
+        assert_eq!(
+            format_date(&date("2024-02-29").unwrap().unwrap().0),
+            "2024-02-29"
+        );
+        assert!(date("2023-02-29").unwrap().is_err());
+        assert!(date("2026-13-01").unwrap().is_err());
+        assert_eq!(date("2026-10-1"), None);
+        assert_eq!(date("2026-10-17x"), None);
+        // Operators right after an invalid time aren't cut through
+        let error = Err("'2026-10-17T25:00' isn't a time of day".to_owned());
+        assert_eq!(date("2026-10-17T25:00÷÷"), Some(error.clone()));
+        assert_eq!(date("2026-10-17T25:00 −1"), Some(error));
+    }
+
+    #[test]
+    fn _format_duration() {
+        let duration = |seconds: i64| format_duration(&Number::from(seconds));
+        assert_eq!(duration(0), "0s");
+        assert_eq!(duration(3 * HOUR + 25 * MINUTE), "3h 25min");
+        assert_eq!(duration(DAY + 5), "1 day 5s");
+        assert_eq!(duration(-90 * DAY), "-(90 days)");
+        let half = Number::Rat(Ratio::new(3.into(), 2.into()));
+        assert_eq!(format_duration(&half), "1.5s");
+    }
+}
@@ -1,8 +1,15 @@
 use crate::number::Number;
+use crate::value::Value;
 
 #[derive(PartialEq, Debug, Clone)]
 pub enum TokenType {
     Literal(Number),
+    // An exponent written as a superscript, e.g. the ² in x²
+    Superscript(Number),
+    // A date, e.g. 2026-10-17
+    Value(Value),
+    // A file name or a column, e.g. "measurements.csv"
+    Text,
     Identifier,
     Symbol,
     Space,
@@ -37,6 +44,11 @@ impl<'t> Token<'t> {
         Self::new(TokenType::Literal(lit), lexeme, column)
     }
 
+    // What's between the quotes of a Text token
+    pub fn text(&self) -> &'t str {
+        self.lexeme.trim_matches('"')
+    }
+
     pub fn sym(lexeme: &'t str, column: usize) -> Self {
         Self::new(TokenType::Symbol, lexeme, column)
     }
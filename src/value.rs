@@ -0,0 +1,308 @@
+use std::fmt;
+
+use num::rational::Ratio;
+use num::{BigInt, Integer, ToPrimitive, Zero};
+
+use crate::currency::{self, Rates};
+use crate::function::Function;
+use crate::number::Number;
+use crate::parser::Parsed;
+use crate::polynomial::Polynomial;
+use crate::time;
+
+// What an expression evaluates to
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(Number),
+    // Seconds since 1970-01-01T00:00:00 UTC
+    Date(Number),
+    // Seconds
+    Duration(Number),
+    // An exact amount and its currency code, e.g. 120 USD
+    Money(Ratio<BigInt>, String),
+    // {1, 2, 3}
+    List(Vec<Number>),
+    // poly(x^3 - 1), with at least one variable
+    Poly(Polynomial),
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Number(n) => n.type_name(),
+            Value::Date(_) => "date",
+            Value::Duration(_) => "duration",
+            Value::Money(_, _) => "money",
+            Value::List(_) => "list",
+            Value::Poly(_) => "polynomial",
+        }
+    }
+
+    pub fn into_number(self) -> Parsed<Number> {
+        match self {
+            Value::Number(n) => Ok(n),
+            other => Err(format!(
+                "Expected a number, found the {} {}",
+                other.type_name(),
+                other
+            )),
+        }
+    }
+
+    // `f` applied to arguments that aren't all numbers, e.g. a date plus a
+    // duration, converting money with `rates`. Binary functions are folded
+    // left to right.
+    pub fn apply(f: &Function, args: Vec<Value>, rates: &Rates) -> Parsed<Value> {
+        let mut args = args.into_iter();
+        let Some(first) = args.next() else {
+            return Err(format!("{} needs an argument", f.name));
+        };
+        if f.arity == 1 {
+            return unary(f, first);
+        }
+        args.try_fold(first, |x, y| binary(f, x, y, rates))
+    }
+}
+
+fn unary(f: &Function, x: Value) -> Parsed<Value> {
+    match (f.name.as_ref(), x) {
+        ("-", Value::Duration(t)) => Ok(Value::Duration(-t)),
+        ("-", Value::Money(a, code)) => Ok(Value::Money(-a, code)),
+        ("-", Value::Poly(p)) => Ok(Value::Poly(-p)),
+        (_, x) => Err(format!("Can't apply {} to a {}", f.name, x.type_name())),
+    }
+}
+
+// Money is only multiplied and divided by integers and rationals, so that
+// amounts stay exact
+fn exact(n: Number) -> Parsed<Ratio<BigInt>> {
+    match n {
+        Number::Int(n) => Ok(Ratio::from_integer(n)),
+        Number::Rat(r) => Ok(r),
+        n => Err(format!("Money needs exact numbers, found the float {}", n)),
+    }
+}
+
+fn nonzero(r: Ratio<BigInt>) -> Parsed<Ratio<BigInt>> {
+    match r.is_zero() {
+        true => Err("Can't divide money by 0".to_owned()),
+        false => Ok(r),
+    }
+}
+
+fn binary(f: &Function, x: Value, y: Value, rates: &Rates) -> Parsed<Value> {
+    use Value::*;
+    match (f.name.as_ref(), x, y) {
+        ("+", Date(d), Duration(t)) | ("+", Duration(t), Date(d)) => Ok(Date(d + t)),
+        ("-", Date(d), Duration(t)) => Ok(Date(d - t)),
+        ("-", Date(a), Date(b)) => Ok(Duration(a - b)),
+        ("+", Duration(a), Duration(b)) => Ok(Duration(a + b)),
+        ("-", Duration(a), Duration(b)) => Ok(Duration(a - b)),
+        // Durations side by side add up, as in 3h 25min, but can't be multiplied
+        ("*", Duration(a), Duration(b)) if f.is_implied_mul() => Ok(Duration(a + b)),
+        ("*", Duration(t), Number(n)) | ("*", Number(n), Duration(t)) => Ok(Duration(t * n)),
+        ("/", Duration(t), Number(n)) => Ok(Duration(t / n)),
+        ("/" | "in", Duration(a), Duration(b)) => Ok(Number(a / b)),
+        // Sums and differences are in the currency on the left
+        ("+", Money(a, x), Money(b, y)) => Ok(Money(a + rates.convert(b, &y, &x)?, x)),
+        ("-", Money(a, x), Money(b, y)) => Ok(Money(a - rates.convert(b, &y, &x)?, x)),
+        ("*", Money(a, code), Number(n)) | ("*", Number(n), Money(a, code)) => {
+            Ok(Money(a * exact(n)?, code))
+        }
+        ("/", Money(a, code), Number(n)) => Ok(Money(a / nonzero(exact(n)?)?, code)),
+        ("/", Money(a, x), Money(b, y)) => {
+            let ratio = a / nonzero(rates.convert(b, &y, &x)?)?;
+            Ok(Number(crate::number::Number::Rat(ratio).try_into_int()))
+        }
+        ("in", Money(a, x), Money(_, y)) => Ok(Money(rates.convert(a, &x, &y)?, y)),
+        ("^", Poly(p), Number(crate::number::Number::Int(n))) => {
+            let n = n
+                .to_u32()
+                .ok_or_else(|| format!("Can't raise a polynomial to the power {}", n))?;
+            Ok(p.pow(n).into())
+        }
+        (name @ ("+" | "-" | "*" | "/" | "%"), x, y) if is_poly(&x) || is_poly(&y) => {
+            let (p, q) = (polynomial(x)?, polynomial(y)?);
+            let result = match name {
+                "+" => &p + &q,
+                "-" => &p - &q,
+                "*" => &p * &q,
+                "/" => p.div_rem(&q)?.0,
+                _ => p.div_rem(&q)?.1,
+            };
+            Ok(result.into())
+        }
+        (_, x, y) => Err(format!(
+            "Can't apply {} to a {} and a {}",
+            f.name,
+            x.type_name(),
+            y.type_name()
+        )),
+    }
+}
+
+fn is_poly(value: &Value) -> bool {
+    matches!(value, Value::Poly(_))
+}
+
+// Numbers are constant polynomials
+fn polynomial(value: Value) -> Parsed<Polynomial> {
+    match value {
+        Value::Poly(p) => Ok(p),
+        value => Ok(Polynomial::constant(value.into_number()?)),
+    }
+}
+
+// Functions of polynomials, which take numbers as constant ones, e.g. poly 5
+
+pub fn poly(args: &[Value]) -> Parsed<Value> {
+    let [p] = args else {
+        return Err(arguments("poly", 1, args));
+    };
+    Ok(polynomial(p.clone())?.into())
+}
+
+// A number has no unknowns to differentiate by, e.g. x^2 once x has a value
+pub fn deriv(args: &[Value]) -> Parsed<Value> {
+    match args {
+        [Value::Poly(p)] => match p.variables()[..] {
+            [x] => Ok(p.derive(x).into()),
+            _ => Err(format!("{} has several variables, use deriv(p, x)", p)),
+        },
+        [Value::Number(n)] => Err(format!("Can't differentiate {}, which has no unknowns", n)),
+        [x] => Err(format!("Can't apply deriv to a {}", x.type_name())),
+        _ => Err(arguments("deriv", 1, args)),
+    }
+}
+
+pub fn deriv_by(args: &[Value]) -> Parsed<Value> {
+    let [p, x] = args else {
+        return Err(arguments("deriv", 2, args));
+    };
+    match x {
+        Value::Poly(x) if x.as_variable().is_some() => {
+            let x = x.as_variable().expect("is a variable");
+            Ok(polynomial(p.clone())?.derive(x).into())
+        }
+        x => Err(format!("Expected a variable to derive by, found {}", x)),
+    }
+}
+
+pub fn degree(args: &[Value]) -> Parsed<Value> {
+    let [p] = args else {
+        return Err(arguments("degree", 1, args));
+    };
+    let degree = polynomial(p.clone())?.degree().unwrap_or(0);
+    Ok(Value::Number(Number::from(degree)))
+}
+
+// The highest power first, as it's written
+pub fn coeffs(args: &[Value]) -> Parsed<Value> {
+    let [p] = args else {
+        return Err(arguments("coeffs", 1, args));
+    };
+    let coefs = polynomial(p.clone())?.coefs()?;
+    Ok(Value::List(coefs.into_iter().rev().collect()))
+}
+
+// Integers have a gcd as well, other numbers don't
+pub fn gcd(args: &[Value]) -> Parsed<Value> {
+    match args {
+        [Value::Number(Number::Int(a)), Value::Number(Number::Int(b))] => {
+            Ok(Value::Number(Number::Int(a.gcd(b))))
+        }
+        [Value::Number(_), Value::Number(_)] => Ok(Value::Number(Number::Flt(f64::NAN))),
+        [p, q] => Ok(polynomial(p.clone())?.gcd(&polynomial(q.clone())?)?.into()),
+        _ => Err(arguments("gcd", 2, args)),
+    }
+}
+
+fn arguments(name: &str, arity: usize, args: &[Value]) -> String {
+    format!(
+        "function {} was called with {} arguments, expected {}",
+        name,
+        args.len(),
+        arity
+    )
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Date(t) => write!(f, "{}", time::format_date(t)),
+            Value::Duration(t) => write!(f, "{}", time::format_duration(t)),
+            Value::Money(amount, code) => write!(f, "{}", currency::format(amount, code)),
+            Value::List(items) => {
+                let items: Vec<_> = items.iter().map(|it| it.to_string()).collect();
+                write!(f, "{{{}}}", items.join(", "))
+            }
+            Value::Poly(p) => write!(f, "{}", p),
+        }
+    }
+}
+
+impl From<Number> for Value {
+    fn from(n: Number) -> Self {
+        Value::Number(n)
+    }
+}
+
+// Polynomials without variables are numbers
+impl From<Polynomial> for Value {
+    fn from(p: Polynomial) -> Self {
+        match p.as_constant() {
+            Some(n) => Value::Number(n),
+            None => Value::Poly(p),
+        }
+    }
+}
+
+impl From<&Number> for Value {
+    fn from(n: &Number) -> Self {
+        Value::Number(n.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::function::{ADD, DIV, MUL, SUB};
+
+    #[test]
+    fn _apply() {
+        let days = |n: i64| Value::Duration(Number::from(n * 86400));
+        let date = Value::Date(Number::from(0));
+        let none = Rates::default();
+        let later = Value::apply(&ADD, vec![date.clone(), days(90)], &none).unwrap();
+        assert_eq!(later.to_string(), "1970-04-01");
+        assert_eq!(
+            Value::apply(&SUB, vec![later, date.clone()], &none),
+            Ok(days(90))
+        );
+        let twice = Value::apply(&MUL, vec![days(1), Value::from(Number::from(2))], &none);
+        assert_eq!(twice, Ok(days(2)));
+        assert!(Value::apply(&ADD, vec![date.clone(), date], &none).is_err());
+    }
+
+    #[test]
+    fn _money() {
+        let rates = Rates::from_csv("USD,1\nEUR,1.08").unwrap();
+        let money = |n: i64, code: &str| Value::Money(Ratio::from_integer(n.into()), code.into());
+        let sum = Value::apply(&ADD, vec![money(100, "USD"), money(50, "EUR")], &rates);
+        assert_eq!(sum, Ok(money(154, "USD")));
+        let third = Value::apply(
+            &DIV,
+            vec![money(1, "USD"), Value::from(Number::from(3))],
+            &rates,
+        );
+        assert_eq!(third.unwrap().to_string(), "0.33 USD");
+        let float = Value::apply(
+            &MUL,
+            vec![money(1, "USD"), Value::from(Number::Flt(0.5))],
+            &rates,
+        );
+        assert!(float.is_err());
+        assert!(Value::apply(&ADD, vec![money(1, "USD"), money(1, "GBP")], &rates).is_err());
+    }
+}
@@ -0,0 +1,124 @@
+use std::fmt;
+
+use num::{BigInt, Integer, One};
+
+use crate::number::Number;
+use crate::parser::Parsed;
+
+// The largest word `:bits` accepts
+const MAX_BITS: u32 = 4096;
+
+// A fixed word size for register math, set with `:bits 32 signed`. Integers
+// are wrapped to it after every operation, like in C.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Word {
+    pub bits: u32,
+    pub signed: bool,
+}
+
+impl Word {
+    // Reads `32`, `32 signed` or `16 unsigned`
+    pub fn parse(text: &str) -> Parsed<Self> {
+        let mut words = text.split_whitespace();
+        let bits = match words.next().map(str::parse) {
+            Some(Ok(bits @ 1..=MAX_BITS)) => bits,
+            _ => return Err(format!("Expected 1 to {} bits, found '{}'", MAX_BITS, text)),
+        };
+        let signed = match words.next() {
+            None | Some("unsigned") => false,
+            Some("signed") => true,
+            Some(other) => return Err(format!("Expected signed or unsigned, found '{}'", other)),
+        };
+        match words.next() {
+            None => Ok(Self { bits, signed }),
+            Some(other) => Err(format!("Unexpected '{}' after '{}'", other, text)),
+        }
+    }
+
+    fn modulus(&self) -> BigInt {
+        BigInt::one() << self.bits
+    }
+
+    // `n` wrapped to the word, and whether it didn't fit. Fractions are
+    // truncated like integer division, and floats are left alone.
+    pub fn wrap(&self, n: Number) -> (Number, bool) {
+        let n = match n {
+            Number::Rat(r) => r.to_integer(),
+            Number::Int(n) => n,
+            n => return (n, false),
+        };
+        let modulus = self.modulus();
+        let mut wrapped = n.mod_floor(&modulus);
+        if self.signed && wrapped >= &modulus >> 1 {
+            wrapped -= modulus;
+        }
+        let overflow = wrapped != n;
+        (Number::Int(wrapped), overflow)
+    }
+
+    // An integer in two's complement in base 2, 8 or 16, with every digit of
+    // the word and `_` between groups of 4, e.g. 0xffff_fffe or 0b1111_1110
+    pub fn show(&self, n: &Number, radix: u32) -> Option<String> {
+        let prefix = match radix {
+            2 => "0b",
+            8 => "0o",
+            16 => "0x",
+            _ => return None,
+        };
+        let Number::Int(n) = n else {
+            return None;
+        };
+        let width = self.bits.div_ceil(radix.trailing_zeros()) as usize;
+        let digits = format!(
+            "{:0>width$}",
+            n.mod_floor(&self.modulus()).to_str_radix(radix),
+            width = width
+        );
+        let groups: Vec<_> = digits
+            .as_bytes()
+            .rchunks(4)
+            .rev()
+            .map(String::from_utf8_lossy)
+            .collect();
+        Some(format!("{}{}", prefix, groups.join("_")))
+    }
+}
+
+impl fmt::Display for Word {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let signed = if self.signed { "signed" } else { "unsigned" };
+        write!(f, "{} {}", self.bits, signed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn _wrap() {
+        let byte = Word::parse("8").unwrap();
+        assert_eq!(byte.wrap(Number::from(255)), (Number::from(255), false));
+        assert_eq!(byte.wrap(Number::from(256)), (Number::from(0), true));
+        assert_eq!(byte.wrap(Number::from(-1)), (Number::from(255), true));
+        let signed = Word::parse("8 signed").unwrap();
+        assert_eq!(signed.wrap(Number::from(128)), (Number::from(-128), true));
+        assert_eq!(signed.wrap(Number::from(-1)), (Number::from(-1), false));
+        assert!(Word::parse("0").is_err());
+        assert!(Word::parse("32 maybe").is_err());
+    }
+
+    #[test]
+    fn _show() {
+        let word = Word::parse("32 signed").unwrap();
+        let show = |n: i64, radix| word.show(&Number::from(n), radix);
+        assert_eq!(show(-2, 16).as_deref(), Some("0xffff_fffe"));
+        assert_eq!(show(255, 16).as_deref(), Some("0x0000_00ff"));
+        assert_eq!(show(255, 10), None);
+        let byte = Word::parse("8").unwrap();
+        assert_eq!(
+            byte.show(&Number::from(5), 2).as_deref(),
+            Some("0b0000_0101")
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use cwim::env::*;
 use cwim::interpreter::run;
 use cwim::number::Number;
+use cwim::value::Value;
 use num::BigInt;
 fn _test_run(text: &str, expected: Number) {
     assert_eq!(run(text, &mut Env::prelude()), Ok(expected));
@@ -14,6 +15,11 @@ fn _test_run_float(text: &str, expected: f64) {
     _test_run(text, Number::Flt(expected))
 }
 
+// Runs `text` in `env`, for values that aren't numbers, e.g. dates or lists
+fn _show(text: &str, env: &mut Env) -> Result<String, String> {
+    cwim::interpreter::run_value(text, env).map(|it| it.to_string())
+}
+
 #[test]
 fn _run_basic() {
     _test_run_int("234*5+7*8-18^3", 234 * 5 + 7 * 8 - (18i64).pow(3));
@@ -38,7 +44,7 @@ fn _plus_minus() {
 
 #[test]
 fn _run_with_spaces_2() {
-    _test_run_int("234*5+7*8-18 ^ 3", (234 * 5 + 7 * 8 as i64 - 18).pow(3));
+    _test_run_int("234*5+7*8-18 ^ 3", (234 * 5 + 7 * 8_i64 - 18).pow(3));
 }
 
 #[test]
@@ -48,9 +54,9 @@ fn _reg_1() {
 
 #[test]
 fn _a() {
-    _test_run_int("6+1*9", 6 + 1 * 9);
-    _test_run_int("6 + 1 * 9", 6 + 1 * 9);
-    _test_run_int("5 + 6 + 1 * 9", 5 + 6 + 1 * 9);
+    _test_run_int("6+1*9", 6 + 9);
+    _test_run_int("6 + 1 * 9", 6 + 9);
+    _test_run_int("5 + 6 + 1 * 9", 5 + 6 + 9);
     _test_run_int("2 ^ 4 * 5 + 6 + 1 ^ 9", 2i64.pow(4) * 5 + 6 + 1i64.pow(9))
 }
 
@@ -66,7 +72,7 @@ fn _run_with_spaces_3() {
 
 #[test]
 fn _run_with_spaces_4() {
-    _test_run_int("234 * 5+7*8-18 ^ 3", 234 * (5 + 7 * 8 - 18 as i64).pow(3));
+    _test_run_int("234 * 5+7*8-18 ^ 3", 234 * (5 + 7 * 8 - 18_i64).pow(3));
 }
 
 #[test]
@@ -86,9 +92,19 @@ fn _implied_multiplication_3() {
     _test_run_int("2 3", 6);
 }
 
+#[test]
+fn _implied_multiplication_of_functions() {
+    // The function takes its argument, and the rest of the line still counts
+    _test_run_float("2 sin 0 + 1", 1.);
+    _test_run_float("2 sqrt 4 + 1", 5.);
+    _test_run_float("1 + 2 sqrt 4 ^ 2", 9.);
+    _test_run_float("2cos 0", 2.);
+    _test_run_float("3 cos(0)^2", 3.);
+}
+
 #[test]
 fn _run_with_parens_3() {
-    _test_run_int("234 *(5+7*8-18) ^ 3", 234 * (5 + 7 * 8 - 18 as i64).pow(3));
+    _test_run_int("234 *(5+7*8-18) ^ 3", 234 * (5 + 7 * 8 - 18_i64).pow(3));
 }
 
 #[test]
@@ -104,6 +120,8 @@ fn _just_a_number() {
 #[test]
 fn _unmatched_parens() {
     assert_eq!(run("4)", &mut Env::prelude()), Ok(Number::from(4)));
+    assert!(run("4) + 1", &mut Env::prelude()).is_err());
+    assert!(run("2 < 3", &mut Env::prelude()).is_err());
 }
 
 #[test]
@@ -131,9 +149,9 @@ fn _unary_and_binary() {
 
 #[test]
 fn _double_unary() {
-    _test_run_float("sin cos 2-2", (1 as f64).sin());
-    _test_run_float("sin(cos 2-2)", (1 as f64).sin());
-    _test_run_float("sin(cos(2-2))", (1 as f64).sin());
+    _test_run_float("sin cos 2-2", 1_f64.sin());
+    _test_run_float("sin(cos 2-2)", 1_f64.sin());
+    _test_run_float("sin(cos(2-2))", 1_f64.sin());
 }
 
 #[test]
@@ -200,5 +218,509 @@ fn _divide_by_zero() {
 
 #[test]
 fn _fractional_exponents() {
-    _test_run_float("2^ -1/2", 0.7071067811865476);
+    _test_run_float("2^ -1/2", std::f64::consts::FRAC_1_SQRT_2);
+}
+
+#[test]
+fn _lazy_definition() {
+    let mut env = Env::prelude();
+    let _ = run("w = 2", &mut env);
+    let _ = run("h = 3", &mut env);
+    assert_eq!(run("area := w * h", &mut env), Ok(Number::from(6)));
+    let _ = run("w = 5", &mut env);
+    assert_eq!(run("area + 1", &mut env), Ok(Number::from(16)));
+    assert_eq!(
+        env.find_value("area"),
+        Ok(cwim::interpreter::Expr::Literal(Number::from(15)))
+    );
+}
+
+#[test]
+fn _lazy_definition_of_unknowns() {
+    let mut env = Env::prelude();
+    // Stored, with a note instead of a value
+    assert!(matches!(
+        cwim::interpreter::run_stmt("double := 2y", &mut env),
+        Ok(cwim::interpreter::Outcome::Pending(_))
+    ));
+    let _ = run("y = 4", &mut env);
+    assert_eq!(run("double", &mut env), Ok(Number::from(8)));
+}
+
+#[test]
+fn _lazy_cycles() {
+    let mut env = Env::prelude();
+    let _ = run("a := b + 1", &mut env);
+    let _ = run("b := 2c", &mut env);
+    assert!(run("c := a", &mut env).is_err());
+    assert!(run("a := a + 1", &mut env).is_err());
+    assert_eq!(env.deps("a"), Ok(vec!["b".to_owned(), "c".to_owned()]));
+    assert_eq!(env.deps("pi"), Ok(vec![]));
+}
+
+#[test]
+fn _history() {
+    let mut env = Env::prelude();
+    for input in ["2+2", "x = 10", "3x"] {
+        let result = run(input, &mut env).unwrap();
+        env.record(input, &result);
+    }
+    assert_eq!(run("$1 + $3", &mut env), Ok(Number::from(34)));
+    assert_eq!(run("_ - __", &mut env), Ok(Number::from(20)));
+    assert_eq!(run("ans", &mut env), Ok(Number::from(30)));
+    assert!(run("$4", &mut env).is_err());
+    assert_eq!(
+        env.history().collect::<Vec<_>>(),
+        vec![
+            ("2+2", &Value::from(Number::from(4))),
+            ("x = 10", &Value::from(Number::from(10))),
+            ("3x", &Value::from(Number::from(30)))
+        ]
+    );
+}
+
+#[test]
+fn _native_functions() {
+    use cwim::error::CwimError;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    let mut env = Env::prelude();
+    let prices = HashMap::from([(1, Number::from(250)), (2, Number::from(90))]);
+    env.register_unary(
+        "price",
+        4,
+        Arc::new(move |id| {
+            let id = id.to_string().parse::<i32>().unwrap_or(0);
+            prices
+                .get(&id)
+                .cloned()
+                .ok_or_else(|| CwimError::Invalid(format!("No product {}", id)))
+        }),
+    );
+    let boxed: Box<cwim::function::BinaryFn> = Box::new(|x, y| Ok(x * Number::from(10) + y));
+    env.register_binary("@", 5, boxed.into());
+    env.register_binary(
+        "hypot",
+        4,
+        Arc::new(|x, y| Ok(Number::Flt(x.f64_or_nan().hypot(y.f64_or_nan())))),
+    );
+    env.register_variadic(
+        "total",
+        4,
+        Arc::new(|xs| Ok(xs.iter().cloned().fold(Number::from(0), |a, b| a + b))),
+    );
+    assert_eq!(run("price 1 + price(2)", &mut env), Ok(Number::from(340)));
+    assert_eq!(run("2price 2", &mut env), Ok(Number::from(180)));
+    assert_eq!(run("price 3", &mut env), Err("No product 3".to_owned()));
+    // Between + and *, with arguments in order
+    assert_eq!(run("1 + 1@2*1", &mut env), Ok(Number::from(13)));
+    assert_eq!(run("hypot(3, 4)", &mut env), Ok(Number::Flt(5.)));
+    assert!(run("hypot 3", &mut env).is_err());
+    assert_eq!(run("2 hypot(3, 4)", &mut env), Ok(Number::Flt(10.)));
+    assert_eq!(run("total(1, 2, 3^2)", &mut env), Ok(Number::from(12)));
+    assert_eq!(run("total 7", &mut env), Ok(Number::from(7)));
+    assert_eq!(run("total()", &mut env), Ok(Number::from(0)));
+    assert!(run("1, 2", &mut env).is_err());
+}
+
+#[test]
+fn _user_operators() {
+    use cwim::function::{Assoc, Function, F};
+    use std::sync::Arc;
+
+    let mut env = Env::prelude();
+    env.infix("<+> 5 (a, b) -> sqrt(a^2+b^2)").unwrap();
+    env.infix("** 7 right (b, n) -> b^n").unwrap();
+    env.infix("// 6 (a, b) -> (a - a%b)/b").unwrap();
+    env.define_infix("<<", 3, Assoc::Left, ["x", "n"], "x*2^n")
+        .unwrap();
+    assert_eq!(run("3 <+> 4", &mut env), Ok(Number::Flt(5.)));
+    assert_eq!(run("1 + 3<+>4", &mut env), Ok(Number::Flt(6.)));
+    assert_eq!(run("2**3**2", &mut env), Ok(Number::from(512)));
+    assert_eq!(run("2**-1", &mut env), Ok(Number::Flt(0.5)));
+    assert_eq!(run("2*-3", &mut env), Ok(Number::from(-6)));
+    assert_eq!(run("17//5//2", &mut env), Ok(Number::from(1)));
+    assert_eq!(run("1+1 << 2+1", &mut env), Ok(Number::from(16)));
+    // The parameters shadow variables
+    run("a = 100", &mut env).unwrap();
+    assert_eq!(run("3 <+> 4 + a", &mut env), Ok(Number::Flt(105.)));
+
+    env.register(
+        Function::native("^^", 7, F::NativeBinary(Arc::new(|x, y| Ok(x - y))))
+            .with_assoc(Assoc::Right),
+    );
+    assert_eq!(run("10 ^^ 4 ^^ 1", &mut env), Ok(Number::from(7)));
+
+    assert!(env.infix("+ 5 (a, b) -> a").is_err());
+    assert!(env.infix("<=> 5 (a, b) -> a").is_err());
+    assert!(env.infix("<-> 5 (a) -> a").is_err());
+    assert!(env.infix("<-> 5 (a, b) -> a <-> b").is_err());
+    env.infix("<-> 5 (a, b) -> a <+> b").unwrap();
+    assert!(env.infix("<+> 5 (a, b) -> a <-> b").is_err());
+    assert_eq!(run("3 <+> 4", &mut env), Ok(Number::Flt(5.)));
+}
+
+#[test]
+fn _right_assoc_pow() {
+    _test_run_int("2^3^2", 512);
+    _test_run_int("2^3 ^2", 64);
+    _test_run_int("(2^3)^2", 64);
+    _test_run_int("-2^2", -4);
+}
+
+#[test]
+fn _unicode() {
+    _test_run_int("2×3", 6);
+    _test_run_int("2·3", 6);
+    _test_run_int("5−2", 3);
+    _test_run_int("−3", -3);
+    _test_run_float("√16", 4.);
+    _test_run_float("2√4", 4.);
+    _test_run_float("2cos 0 + 1", 3.);
+    _test_run_int("3²", 9);
+    _test_run_int("2·3²", 18);
+    _test_run_int("2 - 3² + 1", -6);
+    _test_run_int("2²³", 8388608);
+    assert_eq!(
+        run("6÷4", &mut Env::prelude()),
+        run("3/2", &mut Env::prelude())
+    );
+    assert_eq!(
+        run("π", &mut Env::prelude()),
+        run("pi", &mut Env::prelude())
+    );
+
+    let mut env = Env::prelude();
+    run("θ₀ = 4", &mut env).unwrap();
+    run("v_max = 3", &mut env).unwrap();
+    assert_eq!(run("2θ₀²", &mut env), Ok(Number::from(32)));
+    assert_eq!(run("v_max θ₀", &mut env), Ok(Number::from(12)));
+    assert_eq!(run("θ₀⁻¹", &mut env), run("θ₀^-1", &mut env));
+}
+
+#[test]
+fn _scientific() {
+    _test_run(
+        "6.022e23",
+        Number::from(602_200_000_000_000_000_000_000i128),
+    );
+    _test_run_int("1_000_000 / 1e6", 1);
+    _test_run_int("0x1.8p1", 3);
+    _test_run_float("1.5e3 / 2.", 750.);
+    assert_eq!(
+        run("2e", &mut Env::prelude()),
+        run("2*e", &mut Env::prelude())
+    );
+
+    let mut env = Env::prelude();
+    env.settings.set("si", "on").unwrap();
+    assert_eq!(run("10M / 2k", &mut env), Ok(Number::from(5000)));
+    assert_eq!(run("3n * 1G", &mut env), Ok(Number::from(3)));
+    run("min = 60", &mut env).unwrap();
+    assert_eq!(run("2min", &mut env), Ok(Number::from(120)));
+}
+
+#[test]
+fn _radix() {
+    _test_run_int("36#zz + 3#12", 1300);
+    _test_run_int("12:30:15 * 240", 3001);
+    _test_run_int("0x1.8 * 0b0.1 * 0o0.4 * 16", 6);
+    _test_run_int("0x.000000001 * 16^9", 1);
+
+    let text = |n: &str, radix| run(n, &mut Env::prelude()).unwrap().to_string_radix(radix);
+    assert_eq!(text("-3/2", 16), "-0x1.8");
+    assert_eq!(text("1/3", 16), "0x1/0x3");
+    assert_eq!(text("1295", 36), "36#zz");
+    assert_eq!(text("12.5 + 1/240", 60), "12:30:15");
+    assert_eq!(text("0.375", 2), "0b0.011");
+    assert_eq!(text("0.5", 8), "0o0.4");
+    for (n, radix) in [("0x1.8", 16), ("3#12.1", 3), ("1:30:45", 60), ("2/3", 12)] {
+        let mut env = Env::prelude();
+        let once = text(n, radix);
+        assert_eq!(run(&once, &mut env), run(n, &mut env), "{}", once);
+    }
+}
+
+#[test]
+fn _bitwise() {
+    _test_run_int("0xf0 | 0x0f", 255);
+    _test_run_int("0xff & ~0x0f", 240);
+    _test_run_int("6 xor 3", 5);
+    // Functions of 2 arguments called after a value are multiplied by it
+    _test_run_int("2 gcd(4, 6)", 4);
+    _test_run_int("2gcd(4,6)", 4);
+    _test_run_int("3 quantile({1,2,3},1/2)", 6);
+    _test_run_int("1 << 4 | 1", 17);
+    _test_run_int("1 << 2+1", 8);
+    _test_run_int("-16 >> 2", -4);
+    _test_run_int("popcount 0xff", 8);
+    assert!(run("1.5 & 1", &mut Env::prelude()).unwrap().is_nan());
+}
+
+#[test]
+fn _word() {
+    let mut env = Env::prelude();
+    env.settings.set("bits", "32 signed").unwrap();
+    env.settings.set("base", "16").unwrap();
+    let mut show = |text: &str| {
+        let n = run(text, &mut env).unwrap();
+        (env.settings.show(&n), env.overflow)
+    };
+    assert_eq!(show("0x7fffffff + 1"), ("0x8000_0000".to_owned(), true));
+    assert_eq!(show("-2"), ("0xffff_fffe".to_owned(), false));
+    assert_eq!(show("0xffffffff >> 4"), ("0xffff_ffff".to_owned(), false));
+    assert_eq!(show("7/2"), ("0x0000_0003".to_owned(), false));
+
+    env.settings.set("bits", "16").unwrap();
+    let mut show = |text: &str| {
+        let n = run(text, &mut env).unwrap();
+        (env.settings.show(&n), env.overflow)
+    };
+    assert_eq!(show("~0 >> 4"), ("0x0fff".to_owned(), false));
+    assert_eq!(show("1 << 16"), ("0x0000".to_owned(), true));
+    assert_eq!(show("popcount ~0"), ("0x0010".to_owned(), false));
+    env.settings.set("base", "10").unwrap();
+    assert_eq!(run("0 - 1", &mut env), Ok(Number::from(65535)));
+}
+
+#[test]
+fn _dates() {
+    let mut env = Env::prelude();
+    // 2026-10-18T09:30:00 UTC
+    env.set_clock(|| Number::from(1_792_315_800));
+    assert_eq!(
+        _show("2026-10-17 + 90 days", &mut env).as_deref(),
+        Ok("2027-01-15")
+    );
+    assert_eq!(_show("now", &mut env).as_deref(), Ok("2026-10-18T09:30"));
+    assert_eq!(
+        _show("now - 2026-01-01 in weeks", &mut env).as_deref(),
+        Ok("13939/336")
+    );
+    assert_eq!(_show("3h 25min * 4", &mut env).as_deref(), Ok("13h 40min"));
+    assert_eq!(
+        _show("2024-03-01 - 2024-02-01", &mut env).as_deref(),
+        Ok("29 days")
+    );
+    assert_eq!(
+        _show("2026-10-17T08:00 - 90min", &mut env).as_deref(),
+        Ok("2026-10-17T06:30")
+    );
+    assert_eq!(_show("1 day / 1h", &mut env).as_deref(), Ok("24"));
+    assert_eq!(_show("2026 - 10 - 17", &mut env).as_deref(), Ok("1999"));
+    assert!(_show("2026-10-17 * 2", &mut env).is_err());
+    // Only durations side by side add up
+    assert!(_show("2h * 3h", &mut env).is_err());
+    assert_eq!(_show("(2h)(30min)", &mut env).as_deref(), Ok("2h 30min"));
+    assert!(_show("2026-02-29", &mut env).is_err());
+    assert!(run("1h", &mut env).is_err());
+    // Units only after a number or in, otherwise they're names like any other
+    assert_eq!(_show("3h 30min in min", &mut env).as_deref(), Ok("210"));
+    run("w = 3", &mut env).unwrap();
+    assert!(run("w*h", &mut env).is_err());
+    cwim::interpreter::run_value("p = poly(x^2)", &mut env).unwrap();
+    assert_eq!(_show("p(h)", &mut env).as_deref(), Ok("h²"));
+    assert_eq!(run("min{3, 1}", &mut env), Ok(Number::from(1)));
+}
+
+#[test]
+fn _money() {
+    let mut env = Env::prelude();
+    env.rates =
+        cwim::currency::Rates::from_csv("currency,rate\nUSD,1\nEUR,1.08\nGBP,1.25").unwrap();
+    assert_eq!(
+        _show("120 USD + 80 EUR in GBP", &mut env).as_deref(),
+        Ok("165.12 GBP")
+    );
+    assert_eq!(
+        _show("120 USD + 80 EUR", &mut env).as_deref(),
+        Ok("206.40 USD")
+    );
+    assert_eq!(_show("1 USD / 3 * 3", &mut env).as_deref(), Ok("1.00 USD"));
+    assert_eq!(_show("100EUR / 50USD", &mut env).as_deref(), Ok("54/25"));
+    assert!(_show("1 USD + 1 CHF", &mut env).is_err());
+    assert!(_show("1 USD * 0.5.", &mut env).is_err());
+    assert!(_show("1 USD + 1", &mut env).is_err());
+    // Only codes from ISO 4217 or the rates are money
+    assert!(_show("2 FOO + 1 FOO", &mut env).is_err());
+    env.rates = cwim::currency::Rates::from_csv("USD,1\nFOO,2").unwrap();
+    assert_eq!(_show("1 FOO in USD", &mut env).as_deref(), Ok("2.00 USD"));
+}
+
+#[test]
+fn _lists() {
+    let mut env = Env::prelude();
+    assert_eq!(
+        _show("xs = {3, 1, 4, 1, 5}", &mut env).as_deref(),
+        Ok("{3, 1, 4, 1, 5}")
+    );
+    assert_eq!(_show("mean xs", &mut env).as_deref(), Ok("14/5"));
+    assert_eq!(_show("median(xs) + mode(xs)", &mut env).as_deref(), Ok("4"));
+    assert_eq!(_show("quantile(xs, 1/4)", &mut env).as_deref(), Ok("1"));
+    assert_eq!(_show("sqrt[4, 9]", &mut env).as_deref(), Ok("{2, 3}"));
+    assert_eq!(
+        _show("xs * 2 + 1", &mut env).as_deref(),
+        Ok("{7, 3, 9, 3, 11}")
+    );
+    assert_eq!(_show("sum(xs, 0.5)", &mut env).as_deref(), Ok("29/2"));
+    assert_eq!(_show("min(xs) + max{2, 7}", &mut env).as_deref(), Ok("8"));
+    assert_eq!(_show("90min in h", &mut env).as_deref(), Ok("3/2"));
+    assert_eq!(_show("min{3, 1} + min[4, 2]", &mut env).as_deref(), Ok("3"));
+    assert_eq!(_show("2{1, 2}", &mut env).as_deref(), Ok("{2, 4}"));
+    assert!(_show("{1, 2} + {1, 2, 3}", &mut env).is_err());
+    assert!(_show("{1, 2h}", &mut env).is_err());
+}
+
+#[test]
+fn _load() {
+    // A directory of its own, so that parallel or leftover runs don't collide
+    let dir = std::env::temp_dir().join(format!("cwim_load_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("measurements.csv");
+    std::fs::write(&path, "time,temp\n0,20.5\n60,21.25\n120,\n180,19.75\n").unwrap();
+    let path = path.display();
+    let mut env = Env::prelude();
+    assert_eq!(
+        _show(&format!("data = load(\"{}\", \"temp\")", path), &mut env).as_deref(),
+        Ok("{41/2, 85/4, 79/4}")
+    );
+    assert_eq!(_show("mean data", &mut env).as_deref(), Ok("41/2"));
+    assert_eq!(
+        _show(&format!("count load(\"{}\", 1)", path), &mut env).as_deref(),
+        Ok("4")
+    );
+    assert!(_show(&format!("load(\"{}\", \"pressure\")", path), &mut env).is_err());
+    assert!(_show("load(1)", &mut env).is_err());
+    assert!(_show("load \"data.csv\"", &mut env).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn _fit() {
+    let mut env = Env::prelude();
+    cwim::interpreter::run_value("xs = {0, 1, 2, 3}", &mut env).unwrap();
+    cwim::interpreter::run_value("ys = {1, 3, 7, 13}", &mut env).unwrap();
+    assert_eq!(run("r2(xs, ys, 2)", &mut env), Ok(Number::from(1)));
+    assert_eq!(
+        run("r2(xs, ys, 1)", &mut env),
+        Ok(Number::from(20) / Number::from(21))
+    );
+    // Solved like any equation, then evaluated at the root
+    assert_eq!(
+        run("fit({1, 2, 3}, {2, 4, 7}, 1) = 0", &mut env),
+        Ok(Number::from(4) / Number::from(15))
+    );
+    assert_eq!(
+        _show("p := fit(xs, ys, 2)", &mut env).as_deref(),
+        Ok("x² + x + 1")
+    );
+    cwim::interpreter::run_value("q = p", &mut env).unwrap();
+    assert_eq!(run("q(5)", &mut env), Ok(Number::from(31)));
+    assert!(run("fit(xs, {1, 2}, 1)", &mut env).is_err());
+    assert!(run("fit(xs, ys, 4)", &mut env).is_err());
+    // Exact fits of high degree take too long
+    let many = (0..60)
+        .map(|it| it.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    assert!(run(&format!("fit({{{}}}, {{{}}}, 59)", many, many), &mut env).is_err());
+    assert_eq!(run("5 - 3y = 2", &mut env), Ok(Number::from(1)));
+}
+
+#[test]
+fn _series() {
+    _test_run_int("sum(k^2, k, 1, 100)", 338350);
+    _test_run(
+        "prod(1 - 1/k^2, k, 2, 50)",
+        Number::from(51) / Number::from(100),
+    );
+    _test_run_int("sum(1, 2, 3, 4)", 10);
+    let mut env = Env::prelude();
+    let zeta2 = run("sum(1/k^2, k, 1, inf)", &mut env).unwrap();
+    assert!((zeta2.to_f64().unwrap() - std::f64::consts::PI.powi(2) / 6.).abs() < 1e-9);
+    assert!(run("sum(1/k, k, 1, inf)", &mut env).is_err());
+    // Sums of polynomials up to n are polynomials in n
+    assert_eq!(run("sum(k, k, 1, n) = 55", &mut env), Ok(Number::from(10)));
+    // Terms see the parameters and the word around them
+    env.infix("<+> 5 (a, b) -> sum(k*a, k, 1, b)").unwrap();
+    assert_eq!(run("2 <+> 3", &mut env), Ok(Number::from(12)));
+    env.settings.set("bits", "8 unsigned").unwrap();
+    assert_eq!(run("sum(k, k, 250, 255)", &mut env), Ok(Number::from(235)));
+    assert!(env.overflow);
+    assert_eq!(run("sum(2k, k, 100, 101)", &mut env), Ok(Number::from(146)));
+    assert!(env.overflow);
+}
+
+#[test]
+fn _calculus() {
+    _test_run_int("limit(sin(x)/x, x, 0)", 1);
+    _test_run(
+        "limit((1 - cos x)/x^2, x, 0)",
+        Number::from(1) / Number::from(2),
+    );
+    let mut env = Env::prelude();
+    // Series are polynomials in x, whether x has a value or not
+    assert_eq!(
+        _show("taylor(exp x, x, 0, 3)", &mut env).as_deref(),
+        Ok("1/6·x³ + 1/2·x² + x + 1")
+    );
+    run("x = 1/2", &mut env).unwrap();
+    assert_eq!(
+        _show("taylor(ln x, x, 1, 2)", &mut env).as_deref(),
+        Ok("-1/2·x² + 2x - 3/2")
+    );
+    cwim::interpreter::run_value("t = taylor(exp x, x, 0, 3)", &mut env).unwrap();
+    assert_eq!(
+        run("t(1/2)", &mut env),
+        Ok(Number::from(79) / Number::from(48))
+    );
+    // High orders stay quick, as nothing is differentiated
+    assert!(_show("taylor(tan x, x, 0, 20)", &mut env)
+        .unwrap()
+        .starts_with("443861162/1856156927625·x¹⁹"));
+    assert!(run("taylor(1/x, x, 0, 3)", &mut env).is_err());
+    assert!(run("limit(1/x, x, 0)", &mut env).is_err());
+    assert!(run("taylor(x % 2, x, 0, 3)", &mut env).is_err());
+    // Parameters of operators are bound inside limits and series
+    env.infix("<~> 5 (a, b) -> limit(sin(a x)/x, x, 0) + b")
+        .unwrap();
+    assert_eq!(run("3 <~> 1", &mut env), Ok(Number::from(4)));
+    env.infix("<^> 5 (a, n) -> taylor(exp(a x), x, 0, n)")
+        .unwrap();
+    assert_eq!(_show("2 <^> 2", &mut env).as_deref(), Ok("2x² + 2x + 1"));
+}
+
+#[test]
+fn _polynomials() {
+    let mut env = Env::prelude();
+    assert_eq!(
+        _show("p = poly(x^3 - 1)", &mut env).as_deref(),
+        Ok("x³ - 1")
+    );
+    assert_eq!(_show("p / (x - 1)", &mut env).as_deref(), Ok("x² + x + 1"));
+    assert_eq!(_show("p % (x - 2)", &mut env).as_deref(), Ok("7"));
+    assert_eq!(_show("gcd(p, x^2 - 1)", &mut env).as_deref(), Ok("x - 1"));
+    assert_eq!(_show("deriv p", &mut env).as_deref(), Ok("3x²"));
+    assert_eq!(_show("p(2)", &mut env).as_deref(), Ok("7"));
+    assert_eq!(_show("p(y + 1)", &mut env).as_deref(), Ok("y³ + 3y² + 3y"));
+    assert_eq!(_show("degree p", &mut env).as_deref(), Ok("3"));
+    assert_eq!(_show("coeffs p", &mut env).as_deref(), Ok("{1, 0, 0, -1}"));
+    // Several variables, given values in alphabetical order
+    assert_eq!(
+        _show("q = poly((x + y)^2)", &mut env).as_deref(),
+        Ok("x² + 2x·y + y²")
+    );
+    assert_eq!(_show("q(1, 2)", &mut env).as_deref(), Ok("9"));
+    assert_eq!(_show("deriv(q, y)", &mut env).as_deref(), Ok("2x + 2y"));
+    assert!(_show("deriv q", &mut env).is_err());
+    assert!(_show("q / (x - 1)", &mut env).is_err());
+    // Solved like any other equation, for a single unknown
+    assert!(run("poly(x y) = 1", &mut env).is_err());
+    assert_eq!(run("deriv p = 12", &mut env), Ok(Number::from(2)));
+    // Numbers are constant polynomials, but have nothing to differentiate
+    assert_eq!(_show("coeffs 5", &mut env).as_deref(), Ok("{5}"));
+    run("x = 3", &mut env).unwrap();
+    assert!(_show("deriv(x^2)", &mut env).is_err());
+    assert_eq!(_show("gcd(12, 18)", &mut env).as_deref(), Ok("6"));
 }